@@ -0,0 +1,245 @@
+////////////////////////////////////////////////////////////////////////////////
+//  Module:   adc.rs
+//
+//  Copyright © 2019 Zachary Nielsen
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+////////////////////////////////////////////////////////////////////////////////
+
+////////////////////////////////////////////////////////////////////////////////
+//  Namespaces
+////////////////////////////////////////////////////////////////////////////////
+use crate::{
+    decimal::{format_significant, parse_decimal},
+    expr::MAX_BITS,
+};
+use num_bigint::{BigInt, BigUint};
+use num_rational::BigRational;
+use num_traits::{One, Signed, Zero};
+use std::str::FromStr;
+
+////////////////////////////////////////////////////////////////////////////////
+//  CODE
+////////////////////////////////////////////////////////////////////////////////
+
+/// How an ADC or DAC codes its range
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Coding {
+    /// Code 0 at the low reference, counting up
+    Straight,
+    /// Straight binary over a bipolar range, midscale at 0 V
+    Offset,
+    /// Offset binary with the top bit flipped, so code 0 is midscale
+    Twos,
+}
+
+impl FromStr for Coding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "straight" | "binary" | "unipolar" => Ok(Coding::Straight),
+            "offset" | "offset-binary" => Ok(Coding::Offset),
+            "twos" | "2s" | "twos-complement" => Ok(Coding::Twos),
+            _ => Err(format!(
+                "Unknown coding '{}'. Use straight, offset, or twos",
+                s
+            )),
+        }
+    }
+}
+
+/// An ideal converter: `bits` of resolution over `low` to `high` volts.
+/// Code k (counting from the lowest) is at low + k LSB, with one LSB
+/// (high - low) / 2^bits.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Adc {
+    pub bits: u32,
+    pub low: BigRational,
+    pub high: BigRational,
+    pub coding: Coding,
+}
+
+/// A voltage converted to a code
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quantized {
+    /// The code, as it is sent
+    pub code: BigUint,
+    /// The voltage was out of range, and the code is the nearest end
+    pub clipped: bool,
+    /// Voltage of the code minus the input voltage
+    pub error: BigRational,
+}
+
+impl Adc {
+    ////////////////////////////////////////////////////////////////////////////
+    // NAME:   new
+    //
+    // NOTES:
+    //     A converter from its references.  One voltage is the full scale,
+    //     0 to it for straight binary and - to + it for the bipolar codings.
+    // ARGS:
+    //     bits - resolution
+    //     vref - the full scale, or LOW,HIGH
+    //     coding - how codes map to the range
+    // RETURN: The converter, or what is wrong with the references
+    //
+    pub fn new(bits: u32, vref: &str, coding: Coding) -> Result<Adc, String> {
+        if bits == 0 {
+            return Err(String::from("A converter has at least 1 bit"));
+        }
+        if u64::from(bits) > MAX_BITS {
+            return Err(format!("A converter has at most {} bits", MAX_BITS));
+        }
+        let voltage = |text: &str| {
+            parse_decimal(text.trim())
+                .ok_or_else(|| format!("Could not read {} as a voltage", text))
+        };
+        let (low, high) = match vref.split_once(',') {
+            Some((low, high)) => (voltage(low)?, voltage(high)?),
+            None if coding == Coding::Straight => (BigRational::zero(), voltage(vref)?),
+            None => {
+                let full = voltage(vref)?;
+                (-full.abs(), full.abs())
+            }
+        };
+        if low >= high {
+            return Err(format!("The reference range {} is empty", vref));
+        }
+        Ok(Adc {
+            bits,
+            low,
+            high,
+            coding,
+        })
+    }
+
+    /// The size of one code, in volts
+    pub fn lsb(&self) -> BigRational {
+        (&self.high - &self.low) / BigRational::from_integer(self.levels())
+    }
+
+    /// Number of codes
+    fn levels(&self) -> BigInt {
+        BigInt::one() << self.bits
+    }
+
+    /// Code to its step above the low reference, or back.  Flipping the
+    /// top bit turns two's complement into offset binary either way.
+    fn step(&self, code: &BigInt) -> BigInt {
+        match self.coding {
+            Coding::Twos => code ^ (BigInt::one() << (self.bits - 1)),
+            Coding::Straight | Coding::Offset => code.clone(),
+        }
+    }
+
+    /// The voltage of a code, or an error if it has too many bits
+    pub fn voltage(&self, code: &BigUint) -> Result<BigRational, String> {
+        if code.bits() > u64::from(self.bits) {
+            return Err(format!("Code {} does not fit in {} bits", code, self.bits));
+        }
+        let step = self.step(&BigInt::from(code.clone()));
+        Ok(&self.low + self.lsb() * BigRational::from_integer(step))
+    }
+
+    ////////////////////////////////////////////////////////////////////////////
+    // NAME:   code
+    //
+    // NOTES:
+    //     The code nearest to a voltage.  Voltages out of range get the
+    //     lowest or highest code.
+    // ARGS:
+    //     voltage - the voltage to convert
+    // RETURN: The code, and how far its voltage is from `voltage`
+    //
+    pub fn code(&self, voltage: &BigRational) -> Quantized {
+        let step = ((voltage - &self.low) / self.lsb()).round().to_integer();
+        let max = self.levels() - 1;
+        let clipped = step.is_negative() || step > max;
+        let step = step.clamp(BigInt::zero(), max);
+
+        let code = self.step(&step).magnitude().clone();
+        let error = &self.low + self.lsb() * BigRational::from_integer(step) - voltage;
+        Quantized {
+            code,
+            clipped,
+            error,
+        }
+    }
+
+    /// Writes a quantization error, also as a fraction of one LSB
+    pub fn format_error(&self, error: &BigRational) -> String {
+        let lsbs = error / self.lsb();
+        let sign = if error.is_negative() { "" } else { "+" };
+        format!(
+            "{}{} ({}{} LSB)",
+            sign,
+            format_significant(error, 12),
+            sign,
+            format_significant(&lsbs, 6)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn volts(text: &str) -> BigRational {
+        parse_decimal(text).unwrap()
+    }
+
+    #[test]
+    fn test_new() {
+        let adc = Adc::new(12, "4.096", Coding::Straight).unwrap();
+        assert_eq!(adc.low, volts("0"));
+        assert_eq!(adc.lsb(), volts("0.001"));
+        let adc = Adc::new(16, "2.5", Coding::Twos).unwrap();
+        assert_eq!(adc.low, volts("-2.5"));
+        let adc = Adc::new(8, "0.5, 3", Coding::Offset).unwrap();
+        assert_eq!(adc.high, volts("3"));
+
+        assert!(Adc::new(0, "1", Coding::Straight).is_err());
+        assert!(Adc::new(4_000_000_000, "1", Coding::Straight).is_err());
+        assert!(Adc::new(8, "1,0", Coding::Straight).is_err());
+        assert!(Adc::new(8, "high", Coding::Straight).is_err());
+        assert_eq!("offset".parse(), Ok(Coding::Offset));
+    }
+
+    #[test]
+    fn test_voltage() {
+        let adc = Adc::new(12, "4.096", Coding::Straight).unwrap();
+        assert_eq!(adc.voltage(&BigUint::from(1000u32)), Ok(volts("1")));
+        assert!(adc.voltage(&BigUint::from(4096u32)).is_err());
+
+        let offset = Adc::new(8, "1.28", Coding::Offset).unwrap();
+        let twos = Adc::new(8, "1.28", Coding::Twos).unwrap();
+        assert_eq!(offset.voltage(&BigUint::from(0x80u32)), Ok(volts("0")));
+        assert_eq!(offset.voltage(&BigUint::from(0u32)), Ok(volts("-1.28")));
+        assert_eq!(twos.voltage(&BigUint::from(0u32)), Ok(volts("0")));
+        assert_eq!(twos.voltage(&BigUint::from(0xFFu32)), Ok(volts("-0.01")));
+        assert_eq!(twos.voltage(&BigUint::from(0x80u32)), Ok(volts("-1.28")));
+    }
+
+    #[test]
+    fn test_code() {
+        let adc = Adc::new(12, "4.096", Coding::Straight).unwrap();
+        let quantized = adc.code(&volts("1.0004"));
+        assert_eq!(quantized.code, BigUint::from(1000u32));
+        assert_eq!(quantized.error, volts("-0.0004"));
+        assert!(!quantized.clipped);
+        assert_eq!(adc.format_error(&quantized.error), "-0.0004 (-0.4 LSB)");
+
+        let quantized = adc.code(&volts("5"));
+        assert_eq!(quantized.code, BigUint::from(4095u32));
+        assert!(quantized.clipped);
+
+        let twos = Adc::new(8, "1.28", Coding::Twos).unwrap();
+        assert_eq!(twos.code(&volts("-0.02")).code, BigUint::from(0xFEu32));
+        assert_eq!(twos.code(&volts("-2")).code, BigUint::from(0x80u32));
+    }
+}
@@ -0,0 +1,249 @@
+////////////////////////////////////////////////////////////////////////////////
+//  Module:   alphabet.rs
+//
+//  Copyright © 2019 Zachary Nielsen
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+////////////////////////////////////////////////////////////////////////////////
+
+////////////////////////////////////////////////////////////////////////////////
+//  Namespaces
+////////////////////////////////////////////////////////////////////////////////
+use std::str::FromStr;
+
+////////////////////////////////////////////////////////////////////////////////
+//  CODE
+////////////////////////////////////////////////////////////////////////////////
+
+/// Digits used for bases up to 64 when no alphabet is given.  Up to base 36
+/// letters are read in either case, past that case matters.
+const STANDARD_DIGITS: &str = "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz+/";
+
+pub const MAX_STANDARD_BASE: u32 = 64;
+
+/// Named alphabets: (name, digits, case insensitive)
+const NAMED_ALPHABETS: &[(&str, &str, bool)] = &[
+    ("base32", "ABCDEFGHIJKLMNOPQRSTUVWXYZ234567", true),
+    ("base32hex", "0123456789ABCDEFGHIJKLMNOPQRSTUV", true),
+    ("crockford", "0123456789ABCDEFGHJKMNPQRSTVWXYZ", true),
+    ("base36", "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ", true),
+    (
+        "base58",
+        "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz",
+        false,
+    ),
+    (
+        "base58-flickr",
+        "123456789abcdefghijkmnopqrstuvwxyzABCDEFGHJKLMNPQRSTUVWXYZ",
+        false,
+    ),
+    (
+        "base58-ripple",
+        "rpshnaf39wBUDNEGHJKLM4PQRST7VWXYZ2bcdeCg65jkm8oFqi1tuvAxyz",
+        false,
+    ),
+    (
+        "base62",
+        "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz",
+        false,
+    ),
+    (
+        "base64",
+        "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/",
+        false,
+    ),
+    (
+        "base64url",
+        "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_",
+        false,
+    ),
+];
+
+/// Other spellings of the named alphabets
+const ALIASES: &[(&str, &str)] = &[
+    ("base58-bitcoin", "base58"),
+    ("base58btc", "base58"),
+    ("crockford32", "crockford"),
+    ("base32-crockford", "crockford"),
+];
+
+/// The digits of a base, in order of value
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alphabet {
+    pub name: String,
+    digits: Vec<char>,
+    case_insensitive: bool,
+    /// Extra chars accepted on input, e.g. 'O' for '0' in Crockford base32
+    read_aliases: Vec<(char, u8)>,
+}
+
+impl Alphabet {
+    /// The usual 0-9, A-Z, a-z digits for `base`
+    pub fn standard(base: u32) -> Result<Alphabet, String> {
+        if !(2..=MAX_STANDARD_BASE).contains(&base) {
+            return Err(format!(
+                "Invalid Base.  Base must be between 2 and {} inclusive",
+                MAX_STANDARD_BASE
+            ));
+        }
+        Ok(Alphabet {
+            name: base.to_string(),
+            digits: STANDARD_DIGITS.chars().take(base as usize).collect(),
+            case_insensitive: base <= 36,
+            read_aliases: Vec::new(),
+        })
+    }
+
+    /// One of the well known alphabets, e.g. "base62" or "crockford"
+    pub fn named(name: &str) -> Option<Alphabet> {
+        let lower = name.to_ascii_lowercase();
+        let canonical = ALIASES
+            .iter()
+            .find(|(alias, _)| *alias == lower)
+            .map_or(lower.as_str(), |(_, canonical)| canonical);
+        let &(name, digits, case_insensitive) =
+            NAMED_ALPHABETS.iter().find(|(n, _, _)| *n == canonical)?;
+
+        let read_aliases = if name == "crockford" {
+            vec![('O', 0), ('I', 1), ('L', 1)]
+        } else {
+            Vec::new()
+        };
+        Some(Alphabet {
+            name: name.to_string(),
+            digits: digits.chars().collect(),
+            case_insensitive,
+            read_aliases,
+        })
+    }
+
+    /// An alphabet given digit by digit, e.g. "01" or "0123456789abcdef"
+    pub fn custom(digits: &str) -> Result<Alphabet, String> {
+        let chars: Vec<char> = digits.chars().collect();
+        if chars.len() < 2 {
+            return Err(String::from("An alphabet needs at least 2 digits"));
+        }
+        // Digit values are bytes
+        if chars.len() > 256 {
+            return Err(format!(
+                "An alphabet has at most 256 digits, not {}",
+                chars.len()
+            ));
+        }
+        if let Some((idx, c)) = chars
+            .iter()
+            .enumerate()
+            .find(|(idx, c)| chars[..*idx].contains(c))
+        {
+            return Err(format!("Digit '{}' is repeated at position {}", c, idx));
+        }
+        Ok(Alphabet {
+            name: String::from("custom"),
+            digits: chars,
+            case_insensitive: false,
+            read_aliases: Vec::new(),
+        })
+    }
+
+    /// Number of digits, i.e. the base
+    pub fn base(&self) -> u32 {
+        self.digits.len() as u32
+    }
+
+    /// Character for a digit value
+    pub fn digit_char(&self, digit: u8) -> char {
+        self.digits[digit as usize]
+    }
+
+    /// Value of a digit character, or None if it is not in the alphabet
+    pub fn digit_value(&self, c: char) -> Option<u8> {
+        let matches = |d: char| {
+            if self.case_insensitive {
+                d.eq_ignore_ascii_case(&c)
+            } else {
+                d == c
+            }
+        };
+        self.digits
+            .iter()
+            .position(|&d| matches(d))
+            .or_else(|| {
+                self.read_aliases
+                    .iter()
+                    .find(|(alias, _)| matches(*alias))
+                    .map(|&(_, value)| value as usize)
+            })
+            .map(|v| v as u8)
+    }
+
+    /// True when `c` is one of the digits (so it can not be a sign)
+    pub fn contains(&self, c: char) -> bool {
+        self.digits.contains(&c)
+    }
+}
+
+impl FromStr for Alphabet {
+    type Err = String;
+
+    /// A named alphabet, or else the digits of a custom one
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match Alphabet::named(s) {
+            Some(alphabet) => Ok(alphabet),
+            None => Alphabet::custom(s),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_standard() {
+        let hex = Alphabet::standard(16).unwrap();
+        assert_eq!(hex.digit_value('f'), Some(15));
+        assert_eq!(hex.digit_value('G'), None);
+        assert_eq!(hex.digit_char(10), 'A');
+
+        let base62 = Alphabet::standard(62).unwrap();
+        assert_eq!(base62.digit_value('a'), Some(36));
+        assert_eq!(base62.digit_value('A'), Some(10));
+        assert!(Alphabet::standard(65).is_err());
+        assert!(Alphabet::standard(1).is_err());
+    }
+
+    #[test]
+    fn test_named() {
+        let crockford = Alphabet::named("Crockford32").unwrap();
+        assert_eq!(crockford.base(), 32);
+        assert_eq!(crockford.digit_value('o'), Some(0));
+        assert_eq!(crockford.digit_value('l'), Some(1));
+        assert_eq!(crockford.digit_value('U'), None);
+
+        let btc = Alphabet::named("base58-bitcoin").unwrap();
+        assert_eq!(btc.name, "base58");
+        assert_eq!(btc.digit_value('1'), Some(0));
+        assert_eq!(btc.digit_value('0'), None);
+        assert!(Alphabet::named("base99").is_none());
+    }
+
+    #[test]
+    fn test_custom() {
+        let dna: Alphabet = "ACGT".parse().unwrap();
+        assert_eq!(dna.base(), 4);
+        assert_eq!(dna.digit_value('G'), Some(2));
+        assert!("ABA".parse::<Alphabet>().is_err());
+        assert!("A".parse::<Alphabet>().is_err());
+
+        // Digit values are bytes, so 256 digits at most
+        let wide: String = (0..257u32)
+            .filter_map(|c| char::from_u32(0x100 + c))
+            .collect();
+        assert!(Alphabet::custom(&wide).is_err());
+        assert_eq!(Alphabet::custom(&wide[..512]).map(|a| a.base()), Ok(256));
+    }
+}
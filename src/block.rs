@@ -0,0 +1,191 @@
+////////////////////////////////////////////////////////////////////////////////
+//  Module:   block.rs
+//
+//  Copyright © 2019 Zachary Nielsen
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+////////////////////////////////////////////////////////////////////////////////
+
+////////////////////////////////////////////////////////////////////////////////
+//  Namespaces
+////////////////////////////////////////////////////////////////////////////////
+use crate::endian::{self, ByteOrder};
+use num_bigint::BigUint;
+use std::str::FromStr;
+
+////////////////////////////////////////////////////////////////////////////////
+//  CODE
+////////////////////////////////////////////////////////////////////////////////
+
+/// How the samples of a block are read, like a scope's BN_FMT
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SampleFormat {
+    /// Signed integers (RI)
+    Signed,
+    /// Unsigned integers (RP)
+    Unsigned,
+}
+
+impl FromStr for SampleFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "ri" | "signed" => Ok(SampleFormat::Signed),
+            "rp" | "unsigned" => Ok(SampleFormat::Unsigned),
+            _ => Err(format!(
+                "Unknown sample format '{}'. Use ri (signed) or rp (unsigned)",
+                s
+            )),
+        }
+    }
+}
+
+/// Reads a sample width in bytes, like a scope's BYT_NR
+pub fn sample_width(text: &str) -> Result<usize, String> {
+    match text.trim().parse::<usize>() {
+        Ok(0) => Err(String::from("A sample is at least 1 byte")),
+        Ok(width) => Ok(width),
+        Err(_) => Err(format!("Could not read '{}' as a number of bytes", text)),
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   parse
+//
+// NOTES:
+//     Finds the payload of an IEEE 488.2 arbitrary block.  A definite
+//     block is #, the count of length digits, the length, then the bytes.
+//     An indefinite block is #0, then bytes up to the final newline.
+//     Anything before the # (a SCPI header like :CURVE) is skipped, and
+//     only whitespace may follow a definite block.
+// ARGS:
+//     data - the block as read from the instrument
+// RETURN: The payload bytes, or what is wrong with the block
+//
+pub fn parse(data: &[u8]) -> Result<&[u8], String> {
+    let start = match data.iter().position(|&b| b == b'#') {
+        Some(v) => v + 1,
+        None => return Err(String::from("No # to start a block")),
+    };
+    let digits = match data.get(start) {
+        Some(b) if b.is_ascii_digit() => usize::from(b - b'0'),
+        Some(b) => {
+            return Err(format!(
+                "Expected the count of length digits after #, got '{}'",
+                char::from(*b)
+            ))
+        }
+        None => return Err(String::from("The block ends after #")),
+    };
+    let rest = &data[start + 1..];
+
+    if digits == 0 {
+        // Indefinite length, ended by a newline
+        return Ok(rest.strip_suffix(b"\n").unwrap_or(rest));
+    }
+
+    let len_text = match rest.get(..digits) {
+        Some(v) if v.iter().all(u8::is_ascii_digit) => String::from_utf8_lossy(v),
+        _ => {
+            return Err(format!(
+                "Expected {} length digits after #{}",
+                digits, digits
+            ))
+        }
+    };
+    let len: usize = len_text
+        .parse()
+        .map_err(|_| format!("Block length {} is too big", len_text))?;
+    let payload = &rest[digits..];
+    if payload.len() < len {
+        return Err(format!(
+            "Block says {} bytes, but only {} follow",
+            len,
+            payload.len()
+        ));
+    }
+    if !payload[len..].iter().all(u8::is_ascii_whitespace) {
+        return Err(format!(
+            "{} bytes follow the {} byte block",
+            payload.len() - len,
+            len
+        ));
+    }
+    Ok(&payload[..len])
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   samples
+//
+// NOTES:  Slices a payload into samples, like a scope's BYT_NR and BYT_OR
+// ARGS:
+//     payload - the bytes of the block
+//     width - bytes per sample
+//     order - byte order of each sample
+// RETURN: The bit pattern of each sample, or an error if the payload does
+//         not split into whole samples
+//
+pub fn samples(payload: &[u8], width: usize, order: ByteOrder) -> Result<Vec<BigUint>, String> {
+    if width == 0 || !payload.len().is_multiple_of(width) {
+        return Err(format!(
+            "{} payload bytes do not split into {} byte samples",
+            payload.len(),
+            width
+        ));
+    }
+    // Each swap is its own inverse, so it also undoes BYT_OR
+    let bytes = endian::swap(payload, order, width)?;
+    Ok(bytes.chunks(width).map(BigUint::from_bytes_be).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse(b"#14\x01\x02\x03\x04\n"), Ok(&[1u8, 2, 3, 4][..]));
+        assert_eq!(
+            parse(b":CURVE #213ABCDEFGHIJKLM"),
+            Ok(&b"ABCDEFGHIJKLM"[..])
+        );
+        assert_eq!(parse(b"#0\xFF\x0A\x80\n"), Ok(&[0xFFu8, 0x0A, 0x80][..]));
+        assert_eq!(parse(b"#10"), Ok(&[][..]));
+        assert_eq!(
+            parse(b"#15\x01\x02"),
+            Err(String::from("Block says 5 bytes, but only 2 follow"))
+        );
+        assert!(parse(b"#12\x01\x02\x03").is_err());
+        assert!(parse(b"#A").is_err());
+        assert!(parse(b"#2").is_err());
+        assert!(parse(b"1234").is_err());
+    }
+
+    #[test]
+    fn test_samples() {
+        let payload = [0x01, 0x02, 0xFF, 0xFE];
+        assert_eq!(
+            samples(&payload, 2, ByteOrder::Big),
+            Ok(vec![BigUint::from(0x0102u32), BigUint::from(0xFFFEu32)])
+        );
+        assert_eq!(
+            samples(&payload, 2, ByteOrder::Little),
+            Ok(vec![BigUint::from(0x0201u32), BigUint::from(0xFEFFu32)])
+        );
+        assert_eq!(
+            samples(&payload, 1, ByteOrder::Little).map(|s| s.len()),
+            Ok(4)
+        );
+        assert!(samples(&payload, 3, ByteOrder::Big).is_err());
+        assert_eq!(sample_width("2"), Ok(2));
+        assert!(sample_width("0").is_err());
+        assert!(sample_width("two").is_err());
+        assert_eq!("RI".parse(), Ok(SampleFormat::Signed));
+        assert_eq!("rp".parse(), Ok(SampleFormat::Unsigned));
+    }
+}
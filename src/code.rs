@@ -0,0 +1,253 @@
+////////////////////////////////////////////////////////////////////////////////
+//  Module:   code.rs
+//
+//  Copyright © 2019 Zachary Nielsen
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+////////////////////////////////////////////////////////////////////////////////
+
+////////////////////////////////////////////////////////////////////////////////
+//  Namespaces
+////////////////////////////////////////////////////////////////////////////////
+use num_bigint::{BigInt, BigUint, Sign};
+use num_traits::Zero;
+use std::str::FromStr;
+
+////////////////////////////////////////////////////////////////////////////////
+//  CODE
+////////////////////////////////////////////////////////////////////////////////
+
+/// A binary code used by encoder and display hardware
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Code {
+    /// Reflected binary Gray code, one bit changes between neighbours
+    Gray,
+    /// Packed BCD, one decimal digit per nibble
+    Bcd,
+    /// Unpacked BCD, one decimal digit per byte
+    UnpackedBcd,
+    /// Excess-3, one decimal digit per nibble, each plus 3
+    Excess3,
+    /// The value plus N, like the exponent of a float
+    Excess(u32),
+}
+
+impl Code {
+    /// Name of the code, for the output row
+    pub fn name(&self) -> String {
+        match self {
+            Code::Gray => String::from("Gray"),
+            Code::Bcd => String::from("BCD"),
+            Code::UnpackedBcd => String::from("Unpacked BCD"),
+            Code::Excess3 => String::from("Excess-3"),
+            Code::Excess(n) => format!("Excess-{}", n),
+        }
+    }
+
+    /// Bits per decimal digit, or 1 for the codes of the whole value
+    pub fn digit_bits(&self) -> u32 {
+        match self {
+            Code::Bcd | Code::Excess3 => 4,
+            Code::UnpackedBcd => 8,
+            Code::Gray | Code::Excess(_) => 1,
+        }
+    }
+
+    ////////////////////////////////////////////////////////////////////////////
+    // NAME:   encode
+    //
+    // NOTES:  Writes a value in the code
+    // ARGS:
+    //     value - the value, only excess-N takes negative ones
+    // RETURN: The bit pattern, or an error if the value has no code
+    //
+    pub fn encode(&self, value: &BigInt) -> Result<BigUint, String> {
+        if let Code::Excess(n) = self {
+            return (value + BigInt::from(*n))
+                .to_biguint()
+                .ok_or_else(|| format!("{} is below the range of {}", value, self.name()));
+        }
+        let value = match value.to_biguint() {
+            Some(v) => v,
+            None => return Err(format!("{} can not be negative", self.name())),
+        };
+
+        match self {
+            Code::Gray => Ok(&value ^ (&value >> 1u32)),
+            _ => {
+                // Each decimal digit in its own group of bits
+                let (bits, offset) = self.digit_layout();
+                let mut pattern = BigUint::zero();
+                for digit in value.to_str_radix(10).bytes() {
+                    pattern = (pattern << bits) + BigUint::from(digit - b'0' + offset);
+                }
+                Ok(pattern)
+            }
+        }
+    }
+
+    ////////////////////////////////////////////////////////////////////////////
+    // NAME:   decode
+    //
+    // NOTES:
+    //     Reads a bit pattern in the code.  A group that is not a decimal
+    //     digit is an error, counting digits from the left.
+    // ARGS:
+    //     pattern - the bits in the code
+    // RETURN: The value, or what is wrong with the pattern
+    //
+    pub fn decode(&self, pattern: &BigUint) -> Result<BigInt, String> {
+        match self {
+            Code::Gray => {
+                let mut value = pattern.clone();
+                let mut shifted = pattern >> 1u32;
+                while !shifted.is_zero() {
+                    value ^= &shifted;
+                    shifted >>= 1u32;
+                }
+                Ok(BigInt::from(value))
+            }
+            Code::Excess(n) => Ok(BigInt::from(pattern.clone()) - BigInt::from(*n)),
+            _ => {
+                let (bits, offset) = self.digit_layout();
+                let digits_per_byte = 8 / bits as usize;
+                let mut groups: Vec<u8> = Vec::new();
+                for byte in pattern.to_bytes_be() {
+                    if digits_per_byte == 2 {
+                        groups.push(byte >> 4);
+                        groups.push(byte & 0xF);
+                    } else {
+                        groups.push(byte);
+                    }
+                }
+                // An odd number of nibbles starts with a padding zero
+                if digits_per_byte == 2 && groups.len() > 1 && groups[0] == 0 {
+                    groups.remove(0);
+                }
+
+                let mut value = BigUint::zero();
+                for (idx, &group) in groups.iter().enumerate() {
+                    let digit = match group.checked_sub(offset).filter(|d| *d <= 9) {
+                        Some(v) => v,
+                        None => {
+                            let (kind, width) = if bits == 4 {
+                                ("Nibble", 1)
+                            } else {
+                                ("Byte", 2)
+                            };
+                            return Err(format!(
+                                "{} {:0width$X} at digit {} is not valid {}",
+                                kind,
+                                group,
+                                idx + 1,
+                                self.name(),
+                                width = width
+                            ));
+                        }
+                    };
+                    value = value * 10u32 + digit;
+                }
+                Ok(BigInt::from_biguint(Sign::Plus, value))
+            }
+        }
+    }
+
+    /// Bits per digit, and what is added to each, for the decimal codes
+    fn digit_layout(&self) -> (u32, u8) {
+        match self {
+            Code::Excess3 => (4, 3),
+            Code::UnpackedBcd => (8, 0),
+            _ => (4, 0),
+        }
+    }
+}
+
+impl std::fmt::Display for Code {
+    /// The short name, as read by `from_str`
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Code::Gray => write!(f, "gray"),
+            Code::Bcd => write!(f, "bcd"),
+            Code::UnpackedBcd => write!(f, "ubcd"),
+            Code::Excess3 => write!(f, "xs3"),
+            Code::Excess(n) => write!(f, "excess-{}", n),
+        }
+    }
+}
+
+impl FromStr for Code {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.to_ascii_lowercase();
+        match lower.as_str() {
+            "gray" => Ok(Code::Gray),
+            "bcd" | "packed-bcd" => Ok(Code::Bcd),
+            "ubcd" | "unpacked-bcd" => Ok(Code::UnpackedBcd),
+            "xs3" | "excess-3" | "excess3" => Ok(Code::Excess3),
+            _ => match lower
+                .strip_prefix("excess-")
+                .and_then(|n| n.parse::<u32>().ok())
+            {
+                Some(n) => Ok(Code::Excess(n)),
+                None => Err(format!(
+                    "Unknown code '{}'. Use gray, bcd, ubcd, xs3, or excess-N",
+                    s
+                )),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(code: Code, value: i64) -> Result<BigUint, String> {
+        code.encode(&BigInt::from(value))
+    }
+
+    fn decode(code: Code, pattern: u32) -> Result<BigInt, String> {
+        code.decode(&BigUint::from(pattern))
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode(Code::Gray, 5), Ok(BigUint::from(0b111u32)));
+        assert_eq!(encode(Code::Gray, 8), Ok(BigUint::from(0b1100u32)));
+        assert_eq!(encode(Code::Bcd, 4700), Ok(BigUint::from(0x4700u32)));
+        assert_eq!(encode(Code::UnpackedBcd, 47), Ok(BigUint::from(0x0407u32)));
+        assert_eq!(encode(Code::Excess3, 47), Ok(BigUint::from(0x7Au32)));
+        assert_eq!(encode(Code::Excess(127), -127), Ok(BigUint::from(0u32)));
+        assert!(encode(Code::Excess(127), -128).is_err());
+        assert!(encode(Code::Bcd, -1).is_err());
+    }
+
+    #[test]
+    fn test_decode() {
+        for value in 0..1000 {
+            for &code in &[Code::Gray, Code::Bcd, Code::UnpackedBcd, Code::Excess3] {
+                let pattern = code.encode(&BigInt::from(value)).unwrap();
+                assert_eq!(code.decode(&pattern), Ok(BigInt::from(value)));
+            }
+        }
+        assert_eq!(decode(Code::Bcd, 0x123), Ok(BigInt::from(123)));
+        assert_eq!(decode(Code::Excess(127), 0), Ok(BigInt::from(-127)));
+        assert_eq!(
+            decode(Code::Bcd, 0x4A07),
+            Err(String::from("Nibble A at digit 2 is not valid BCD"))
+        );
+        assert_eq!(
+            decode(Code::UnpackedBcd, 0x0431),
+            Err(String::from("Byte 31 at digit 2 is not valid Unpacked BCD"))
+        );
+        assert!(decode(Code::Excess3, 0x42).is_err());
+        assert_eq!("excess-127".parse(), Ok(Code::Excess(127)));
+        assert_eq!(Code::UnpackedBcd.to_string().parse(), Ok(Code::UnpackedBcd));
+        assert!("excess".parse::<Code>().is_err());
+    }
+}
@@ -0,0 +1,252 @@
+////////////////////////////////////////////////////////////////////////////////
+//  Module:   config.rs
+//
+//  Copyright © 2019 Zachary Nielsen
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+////////////////////////////////////////////////////////////////////////////////
+
+////////////////////////////////////////////////////////////////////////////////
+//  Namespaces
+////////////////////////////////////////////////////////////////////////////////
+use crate::{repl, Opt};
+use numconverter::{Error, ErrorCode};
+use std::path::PathBuf;
+use structopt::clap::ArgMatches;
+use toml::{value::Table, Value};
+
+////////////////////////////////////////////////////////////////////////////////
+//  CODE
+////////////////////////////////////////////////////////////////////////////////
+
+/// Setting for the bases to convert to when none are given
+const DEFAULT_TARGETS: &str = "to";
+
+/// Config file settings, named like the interactive mode ones, and the
+/// argument each stands in for.  An argument given on the command line
+/// wins over the setting.
+const SETTINGS: &[(&str, &str)] = &[
+    ("base", "from-base"),
+    ("from", "from-base"),
+    ("from-base", "from-base"),
+    ("alphabet", "from-alphabet"),
+    ("from-alphabet", "from-alphabet"),
+    ("sep-map", "sep-map"),
+    ("pad-map", "pad-map"),
+    ("sep-length", "sep-length"),
+    ("sep-char", "sep-char"),
+    ("no-sep", "no-sep"),
+    ("bare", "bare"),
+    ("expr", "expr"),
+    ("str", "is-string"),
+    ("text", "text"),
+    ("utf", "utf"),
+    ("join", "join"),
+    ("fourcc", "fourcc"),
+    ("fourcc-size", "fourcc-size"),
+    ("fourcc-endian", "fourcc-endian"),
+    ("byt-nr", "byt-nr"),
+    ("byt-or", "byt-or"),
+    ("bn-fmt", "bn-fmt"),
+    ("width", "width"),
+    ("signed-repr", "signed-repr"),
+    ("float", "float"),
+    ("q", "q-format"),
+    ("endian", "endian"),
+    ("layout", "layout"),
+    ("preamble", "preamble"),
+    ("ymult", "ymult"),
+    ("yoff", "yoff"),
+    ("yzero", "yzero"),
+    ("xincr", "xincr"),
+    ("xzero", "xzero"),
+    ("pt-off", "pt-off"),
+    ("adc", "adc"),
+    ("vref", "vref"),
+    ("coding", "coding"),
+    ("scaled", "scaled"),
+    ("from-code", "from-code"),
+    ("format", "format"),
+];
+
+/// ~/.config/numconverter/config.toml
+pub fn default_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| {
+        home.join(".config")
+            .join("numconverter")
+            .join("config.toml")
+    })
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   apply
+//
+// NOTES:
+//     Fills in the options not given on the command line from the config
+//     file: first its top level settings, then those of --profile.  A
+//     missing config file is only an error when it, or a profile, was
+//     asked for.
+// ARGS:
+//     opt - command line options, updated in place
+//     matches - the parsed arguments, to tell which options were given
+// RETURN: Nothing, or what is wrong with the config file
+//
+pub fn apply(opt: &mut Opt, matches: &ArgMatches) -> Result<(), Error> {
+    let path = match opt.config.as_ref().map(PathBuf::from).or_else(default_path) {
+        Some(v) => v,
+        None => return Ok(()),
+    };
+    let text = match std::fs::read_to_string(&path) {
+        Ok(v) => v,
+        Err(_) if opt.config.is_none() && opt.profile.is_none() => return Ok(()),
+        Err(e) => {
+            return Err(Error::new(
+                ErrorCode::ConfigErr,
+                format!("Could not read config {}: {}", path.display(), e),
+            ));
+        }
+    };
+    apply_text(opt, matches, &text, &path.display().to_string())
+}
+
+/// `apply`, with the config file already read
+fn apply_text(opt: &mut Opt, matches: &ArgMatches, text: &str, source: &str) -> Result<(), Error> {
+    let config_error = |message: String| Error::new(ErrorCode::ConfigErr, message);
+    let mut settings: Table =
+        toml::from_str(text).map_err(|e| config_error(format!("{}: {}", source, e)))?;
+    let profiles = match settings.remove("profiles") {
+        Some(Value::Table(v)) => v,
+        Some(_) => {
+            return Err(config_error(format!(
+                "{}: profiles must be a table",
+                source
+            )))
+        }
+        None => Table::new(),
+    };
+
+    if let Some(name) = &opt.profile {
+        match profiles.get(name) {
+            Some(Value::Table(profile)) => settings.extend(profile.clone()),
+            _ => {
+                let known: Vec<&str> = profiles.keys().map(|k| k.as_str()).collect();
+                let error = config_error(format!("No profile {} in {}", name, source));
+                return Err(if known.is_empty() {
+                    error.with_suggestion("add one under [profiles.NAME]")
+                } else {
+                    error.with_suggestion(format!("profiles are {}", known.join(", ")))
+                });
+            }
+        }
+    }
+
+    for (name, value) in &settings {
+        apply_setting(opt, matches, name, value)
+            .map_err(|e| config_error(format!("{} in {}: {}", name, source, e)))?;
+    }
+    Ok(())
+}
+
+/// Sets one config file setting, unless its option was given
+fn apply_setting(
+    opt: &mut Opt,
+    matches: &ArgMatches,
+    name: &str,
+    value: &Value,
+) -> Result<(), String> {
+    let words = match value {
+        Value::Array(items) => items
+            .iter()
+            .map(word)
+            .collect::<Result<Vec<String>, String>>()?,
+        other => vec![word(other)?],
+    };
+    if name == DEFAULT_TARGETS {
+        opt.default_to = words;
+        return Ok(());
+    }
+
+    match SETTINGS.iter().find(|(setting, _)| *setting == name) {
+        Some((_, arg)) if matches.occurrences_of(arg) > 0 => Ok(()),
+        // Lists are comma separated everywhere but the targets
+        Some(_) => repl::set(opt, name, &[words.join(",").as_str()]),
+        None => Err(String::from("unknown setting")),
+    }
+}
+
+/// A setting value as it would be typed
+fn word(value: &Value) -> Result<String, String> {
+    match value {
+        Value::String(v) => Ok(v.clone()),
+        Value::Integer(v) => Ok(v.to_string()),
+        Value::Boolean(v) => Ok(String::from(if *v { "on" } else { "off" })),
+        other => Err(format!(
+            "expected a string, number, or true/false, got {}",
+            other.type_str()
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use numconverter::output::OutputFormat;
+    use structopt::StructOpt;
+
+    const CONFIG: &str = r#"
+        to = ["16", "2"]
+        sep-map = "2:8"
+        format = "json"
+
+        [profiles.fw]
+        from = 16
+        width = 32
+        sep-char = "_"
+        endian = ["little", "word"]
+        expr = true
+    "#;
+
+    fn configured(args: &[&str], text: &str) -> Result<Opt, Error> {
+        let matches = Opt::clap().get_matches_from(args);
+        let mut opt = Opt::from_clap(&matches);
+        apply_text(&mut opt, &matches, text, "config.toml")?;
+        Ok(opt)
+    }
+
+    #[test]
+    fn test_defaults() {
+        let opt = configured(&["numconverter", "255"], CONFIG).unwrap();
+        assert_eq!(opt.default_to, vec!["16", "2"]);
+        assert_eq!(opt.sep_map, "2:8");
+        assert_eq!(opt.width, None);
+
+        // The command line wins
+        let opt = configured(&["numconverter", "--format", "csv", "255"], CONFIG).unwrap();
+        assert_eq!(opt.format, OutputFormat::Csv);
+    }
+
+    #[test]
+    fn test_profile() {
+        let opt = configured(
+            &["numconverter", "--profile", "fw", "--sep-char", ".", "FF"],
+            CONFIG,
+        )
+        .unwrap();
+        assert_eq!(opt.from_base, 16);
+        assert_eq!(opt.width, Some(32));
+        assert_eq!(opt.sep_char, '.');
+        assert_eq!(opt.endian.len(), 2);
+        assert!(opt.expr);
+        assert_eq!(opt.sep_map, "2:8");
+
+        let err = configured(&["numconverter", "--profile", "hw", "FF"], CONFIG).unwrap_err();
+        assert_eq!(err.code, ErrorCode::ConfigErr);
+        assert_eq!(err.suggestion, Some(String::from("profiles are fw")));
+        assert!(configured(&["numconverter", "1"], "colour = 1").is_err());
+        assert!(configured(&["numconverter", "1"], "width = 1.5").is_err());
+    }
+}
@@ -0,0 +1,1397 @@
+////////////////////////////////////////////////////////////////////////////////
+//  Module:   convert.rs
+//
+//  Copyright © 2019 Zachary Nielsen
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+////////////////////////////////////////////////////////////////////////////////
+
+////////////////////////////////////////////////////////////////////////////////
+//  Namespaces
+////////////////////////////////////////////////////////////////////////////////
+use crate::{
+    adc::{Adc, Coding},
+    alphabet::Alphabet,
+    as_bytes, as_string_base_with, block,
+    block::SampleFormat,
+    byte_swap,
+    code::Code,
+    convert_to_base_10_with, decimal,
+    encoding::{self, Encoding},
+    endian::{self, ByteOrder},
+    expr,
+    fixed::{self, Overflow, QFormat, Rounding},
+    float::{self, FloatFormat},
+    fourcc, fraction,
+    layout::Layout,
+    output::{Record, Row},
+    parse_hex_string, prefix,
+    signed::{self, SignedRepr},
+    unicode::{self, Utf},
+    waveform::Preamble,
+    Error, ErrorCode, Number, Separators,
+};
+use num_bigint::{BigInt, BigUint};
+use num_rational::BigRational;
+use num_traits::{Signed, ToPrimitive};
+use std::collections::HashMap;
+
+////////////////////////////////////////////////////////////////////////////////
+//  CODE
+////////////////////////////////////////////////////////////////////////////////
+
+/// What to convert, and how.  `Options::default()` reads a decimal number
+/// and shows it in base 2, 10, and 16, as the command line does.
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// The number to convert, or the text, expression, or block name
+    pub input: String,
+    /// Base of the input, when there is no `from_alphabet`
+    pub from_base: u32,
+    /// Digits of the input
+    pub from_alphabet: Option<Alphabet>,
+    /// Bit width for signed numbers
+    pub width: Option<u32>,
+    /// Bases and named targets to convert to, the defaults if empty
+    pub targets: Vec<String>,
+    /// Extra alphabets to convert to
+    pub to_alphabet: Vec<Alphabet>,
+    /// Targets used in place of base 2, 10, and 16 when none are given
+    pub default_to: Vec<String>,
+    /// Grouping and padding of the digits
+    pub separators: Separators,
+    /// Show the digits without separators
+    pub no_sep: bool,
+    /// Maximum number of digits after the radix point
+    pub frac_digits: usize,
+    /// Read SI and IEC prefixes on decimal input, like 4k7 or 64Ki
+    pub prefixes: bool,
+    /// The input is an expression
+    pub expr: bool,
+    /// The input is a list of numbers, one per byte
+    pub is_string: bool,
+    /// The input is text, converted to its code units in `utf`
+    pub text: bool,
+    pub utf: Option<Utf>,
+    /// Show a string as one number, its bytes in this order
+    pub join: Option<ByteOrder>,
+    /// The input is a byte string in this encoding
+    pub from_encoding: Option<Encoding>,
+    /// The input is a bit pattern in this code
+    pub from_code: Option<Code>,
+    /// Show the input as a FourCC code, or a code as its number
+    pub fourcc: bool,
+    pub fourcc_size: usize,
+    pub fourcc_endian: ByteOrder,
+    /// An IEEE 488.2 binary block, whose samples are converted
+    pub block: Option<Vec<u8>>,
+    pub byt_nr: usize,
+    pub byt_or: ByteOrder,
+    pub bn_fmt: SampleFormat,
+    /// Representation of negative numbers at `width`
+    pub signed_repr: SignedRepr,
+    pub float: Option<FloatFormat>,
+    pub q_format: Option<QFormat>,
+    pub rounding: Rounding,
+    pub overflow: Overflow,
+    /// Register layout, and NAME=VALUE fields to set in the input
+    pub layout: Option<Layout>,
+    pub field: Vec<String>,
+    /// Waveform scaling for the Y and T targets
+    pub preamble: Option<Preamble>,
+    /// Converter for the V, LSB, and VERR targets
+    pub adc: Option<Adc>,
+    /// The input is a scaled value, converted back to its code
+    pub scaled: bool,
+    /// Byte orders to also show each target in
+    pub endian: Vec<ByteOrder>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            input: String::new(),
+            from_base: 10,
+            from_alphabet: None,
+            width: None,
+            targets: Vec::new(),
+            to_alphabet: Vec::new(),
+            default_to: Vec::new(),
+            separators: Separators::default(),
+            no_sep: false,
+            frac_digits: 32,
+            prefixes: false,
+            expr: false,
+            is_string: false,
+            text: false,
+            utf: None,
+            join: None,
+            from_encoding: None,
+            from_code: None,
+            fourcc: false,
+            fourcc_size: 4,
+            fourcc_endian: ByteOrder::Little,
+            block: None,
+            byt_nr: 1,
+            byt_or: ByteOrder::Big,
+            bn_fmt: SampleFormat::Signed,
+            signed_repr: SignedRepr::TwosComplement,
+            float: None,
+            q_format: None,
+            rounding: Rounding::Nearest,
+            overflow: Overflow::Saturate,
+            layout: None,
+            field: Vec::new(),
+            preamble: None,
+            adc: None,
+            scaled: false,
+            endian: Vec::new(),
+        }
+    }
+}
+
+/// The input, read as the values each target shows
+struct Input {
+    /// One value, or one per item of a string
+    nums: Vec<BigUint>,
+    /// Set when a negative number is shown without a --width
+    negative: bool,
+    /// Set when the input has a radix point
+    fraction: Option<BigRational>,
+    /// Bit patterns are shown in full at this width
+    pattern_width: Option<u32>,
+    /// The items are shown apart, like -s input
+    is_string: bool,
+    /// Set when a real number was quantized to --q
+    q_error: Option<BigRational>,
+    /// Set when a voltage was converted to an --adc code
+    adc_error: Option<BigRational>,
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   convert
+//
+// NOTES:
+//     Converts the input to every target, adding a row to `record` for
+//     each.  The rows made before an error are kept.  Warnings, like a
+//     value clipped to the --q range, are kept in `record` too.
+// ARGS:
+//     options - what to convert, and how
+//     record - gets the input, its base, and the rows
+// RETURN: The value, if it was a single whole number, or the first error
+//
+pub fn convert(options: &Options, record: &mut Record) -> Result<Option<Number>, Error> {
+    let sep_char = options.separators.sep_char;
+    let from_num = options.input.as_str();
+    let from_alphabet = match &options.from_alphabet {
+        Some(v) => v.clone(),
+        None => match Alphabet::standard(options.from_base) {
+            Ok(v) => v,
+            Err(e) => {
+                return Err(Error::new(
+                    ErrorCode::InputBaseErr,
+                    format!("Error with input base: {}", e),
+                ));
+            }
+        },
+    };
+    // Only plain decimal input can be a real number (for --float and --q)
+    let decimal_input = from_alphabet.base() == 10 && options.from_alphabet.is_none();
+
+    // Extra alphabets to output, by name
+    let mut targets: Vec<String> = options.targets.clone();
+    let mut alphabets: HashMap<String, Alphabet> = HashMap::new();
+    for alphabet in &options.to_alphabet {
+        let key = if alphabets.contains_key(&alphabet.name) {
+            format!("{}{}", alphabet.name, alphabets.len())
+        } else {
+            alphabet.name.clone()
+        };
+        alphabets.insert(key.clone(), alphabet.clone());
+        targets.push(key);
+    }
+    let explicit_targets = targets.len() > options.to_alphabet.len();
+    // Decoded byte strings are shown like -s input, one number per byte,
+    // and block samples one number per sample
+    let is_string = (options.is_string
+        || options.text
+        || options.from_encoding.is_some()
+        || options.block.is_some())
+        && !options.fourcc;
+
+    if options.layout.is_none() && !options.field.is_empty() {
+        return Err(Error::new(ErrorCode::LayoutErr, "--field needs a --layout"));
+    }
+    if options.scaled && options.preamble.is_none() && options.adc.is_none() {
+        return Err(Error::new(
+            ErrorCode::WaveformErr,
+            "--scaled needs --adc, a --preamble, or --ymult style options",
+        ));
+    }
+    // A decimal number with an SI or IEC prefix, like 4k7 or 64Ki
+    let plain_number = !options.expr
+        && !is_string
+        && !options.fourcc
+        && !options.scaled
+        && options.float.is_none()
+        && options.q_format.is_none();
+    let prefixed = Some(from_num.replace(sep_char, ""))
+        .filter(|_| options.prefixes && decimal_input && plain_number)
+        .and_then(|num| prefix::parse(&num));
+
+    if !explicit_targets {
+        default_targets(
+            options,
+            is_string,
+            decimal_input,
+            prefixed.as_ref(),
+            &mut targets,
+            &mut alphabets,
+        );
+    }
+
+    let pattern_width = pattern_width(options, is_string);
+    record.input = from_num.trim().to_string();
+    record.input_base = if options.text {
+        String::from("text")
+    } else if options.block.is_some() {
+        String::from("block")
+    } else {
+        from_alphabet.name.clone()
+    };
+    record.width = pattern_width;
+
+    let mut input = Input {
+        nums: Vec::new(),
+        negative: false,
+        fraction: None,
+        pattern_width,
+        is_string,
+        q_error: None,
+        adc_error: None,
+    };
+    input.nums = read_input(
+        options,
+        &from_alphabet,
+        decimal_input,
+        prefixed,
+        &mut input,
+        record,
+    )?;
+    let input = match options.join {
+        Some(order) if input.is_string => join(options, input, order)?,
+        _ => input,
+    };
+    record.width = input.pattern_width;
+
+    // The value, when it is a single whole number
+    let value = Some(&input.nums)
+        .filter(|v| input.fraction.is_none() && v.len() == 1)
+        .map(|v| {
+            let value = BigInt::from(v[0].clone());
+            Number::from(if input.negative { -value } else { value })
+        });
+
+    // Byte swapped copies of the input, each shown as extra rows per target
+    if !options.endian.is_empty() && (input.negative || input.fraction.is_some()) {
+        return Err(Error::new(
+            ErrorCode::WidthErr,
+            "Only whole, non-negative numbers or --width patterns can be byte swapped",
+        ));
+    }
+    let mut views: Vec<(Option<ByteOrder>, Vec<BigUint>)> = vec![(None, input.nums.clone())];
+    for &order in options.endian.iter().filter(|&&o| o != ByteOrder::Big) {
+        let swapped = byte_swap(&input.nums, order, input.is_string, input.pattern_width)?;
+        views.push((Some(order), swapped));
+    }
+
+    // Print conversions
+    for target in targets {
+        for (order, nums) in &views {
+            // The error made quantizing does not change with byte order
+            if order.is_some() && target.eq_ignore_ascii_case("QERR") {
+                continue;
+            }
+            for mut row in target_rows(&target, options, &input, nums, &alphabets)? {
+                if let Some(order) = order {
+                    row.base = format!("{} {}", row.base, order.label());
+                }
+                record.rows.push(row);
+            }
+        }
+    }
+    Ok(value)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   default_targets
+//
+// NOTES:  Adds the targets shown when none are given, which depend on the
+//         kind of input
+// ARGS:
+//     options - what to convert, and how
+//     is_string - the input is a list of items
+//     decimal_input - the input is a plain decimal number
+//     prefixed - the value of a number with an SI or IEC prefix
+//     targets - the targets so far, the --to-alphabet ones
+//     alphabets - the alphabets of the targets, by name
+//
+fn default_targets(
+    options: &Options,
+    is_string: bool,
+    decimal_input: bool,
+    prefixed: Option<&BigRational>,
+    targets: &mut Vec<String>,
+    alphabets: &mut HashMap<String, Alphabet>,
+) {
+    if let Some(code) = options.from_code {
+        // Show the input again in its code
+        targets.insert(0, code.to_string());
+    }
+
+    if let Some(alphabet) = &options.from_alphabet {
+        // Show the input again, so tokens can be checked to round trip
+        alphabets.insert(alphabet.name.clone(), alphabet.clone());
+        targets.insert(0, alphabet.name.clone());
+    }
+
+    if (is_string && options.block.is_none()) || options.fourcc {
+        targets.push("A".to_string());
+    }
+
+    if (options.text || options.utf.is_some()) && options.join.is_none() && !options.fourcc {
+        targets.push("CP".to_string());
+    }
+
+    if options.block.is_some() {
+        targets.push(match options.bn_fmt {
+            SampleFormat::Signed => "S".to_string(),
+            SampleFormat::Unsigned => "U".to_string(),
+        });
+        targets.push("16".to_string());
+    } else if let Some(fmt) = options.q_format.filter(|_| !is_string && !options.fourcc) {
+        targets.append(&mut vec![
+            "2".to_string(),
+            "10".to_string(),
+            "16".to_string(),
+        ]);
+        if fmt.signed {
+            targets.push("S".to_string());
+        }
+        targets.push("Q".to_string());
+        if decimal_input {
+            targets.push("QERR".to_string());
+        }
+    } else if options.float.is_some() && !is_string && !options.fourcc {
+        targets.append(&mut vec![
+            "2".to_string(),
+            "16".to_string(),
+            "F".to_string(),
+            "FIELDS".to_string(),
+        ])
+    } else if !options.fourcc && !options.default_to.is_empty() {
+        targets.extend(options.default_to.iter().cloned());
+    } else if !options.fourcc {
+        targets.append(&mut vec![
+            "2".to_string(),
+            "10".to_string(),
+            "16".to_string(),
+        ])
+    } else {
+        targets.append(&mut vec!["10".to_string(), "16".to_string()])
+    }
+
+    if options.width.is_some() && !is_string && !options.fourcc {
+        targets.append(&mut vec!["S".to_string(), "U".to_string()])
+    }
+
+    if options.layout.is_some() && !is_string && !options.fourcc {
+        targets.push("L".to_string());
+    }
+
+    if let Some(value) = prefixed {
+        targets.append(&mut vec!["ENG".to_string(), "SI".to_string()]);
+        if value.is_integer() {
+            targets.push("IEC".to_string());
+        }
+    }
+
+    if let Some(adc) = &options.adc {
+        // Block samples already have their signed row
+        if adc.coding == Coding::Twos && options.block.is_none() {
+            targets.push("S".to_string());
+        }
+        targets.append(&mut vec!["V".to_string(), "LSB".to_string()]);
+        if options.scaled {
+            targets.push("VERR".to_string());
+        }
+    }
+
+    if options.preamble.is_some() {
+        targets.push("Y".to_string());
+        if is_string {
+            targets.push("T".to_string());
+        }
+    }
+}
+
+/// Width the bit patterns are shown at, from --width or the format of
+/// the input
+fn pattern_width(options: &Options, is_string: bool) -> Option<u32> {
+    options
+        .width
+        .or_else(|| options.float.map(|f| f.bits()))
+        .or_else(|| options.q_format.map(|f| f.bits()))
+        .or_else(|| options.layout.as_ref().and_then(|l| l.width))
+        .or_else(|| options.adc.as_ref().map(|a| a.bits))
+        .or_else(|| Some(options.fourcc_size as u32 * 8).filter(|_| options.fourcc))
+        .or_else(|| {
+            Some(options.byt_nr as u32 * 8)
+                .filter(|_| options.block.is_some() || (is_string && options.byt_nr > 1))
+        })
+        .or_else(|| {
+            options
+                .utf
+                .filter(|u| is_string && u.unit_bits() > 8)
+                .map(|u| u.unit_bits())
+        })
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   read_input
+//
+// NOTES:
+//     Reads the input as the values to convert.  What else is learned
+//     reading it, like the sign or the --q error, is set in `input`.
+// ARGS:
+//     options - what to convert, and how
+//     from_alphabet - the digits of the input
+//     decimal_input - the input is a plain decimal number
+//     prefixed - the value of a number with an SI or IEC prefix
+//     input - the input so far
+//     record - gets the warnings
+// RETURN: The values, or an error
+//
+fn read_input(
+    options: &Options,
+    from_alphabet: &Alphabet,
+    decimal_input: bool,
+    prefixed: Option<BigRational>,
+    input: &mut Input,
+    record: &mut Record,
+) -> Result<Vec<BigUint>, Error> {
+    let sep_char = options.separators.sep_char;
+    let from_num = options.input.as_str();
+    let width = options.width;
+
+    let nums = if let Some(enc) = options.from_encoding {
+        match encoding::decode(from_num, enc) {
+            Ok(bytes) => bytes.into_iter().map(BigUint::from).collect(),
+            Err(e) => {
+                return Err(Error::new(
+                    ErrorCode::EncodingErr,
+                    format!("Could not decode {} as {}: {}", from_num, enc.name(), e),
+                ));
+            }
+        }
+    } else if options.fourcc {
+        // A number to show as its code, or a code to show as its number
+        let from_code = |e: Error| {
+            fourcc::from_code(from_num, options.fourcc_size, options.fourcc_endian).map_err(|_| e)
+        };
+        let value = if options.text {
+            fourcc::from_code(from_num, options.fourcc_size, options.fourcc_endian)
+                .map_err(|e| Error::new(ErrorCode::EncodingErr, e))?
+        } else {
+            convert_to_base_10_with(from_num, from_alphabet, sep_char).or_else(from_code)?
+        };
+        vec![value]
+    } else if let Some(data) = &options.block {
+        match block::parse(data)
+            .and_then(|payload| block::samples(payload, options.byt_nr, options.byt_or))
+        {
+            Ok(v) => v,
+            Err(e) => {
+                return Err(Error::new(
+                    ErrorCode::BlockErr,
+                    format!("Could not read the block in {}: {}", from_num, e),
+                ));
+            }
+        }
+    } else if options.text {
+        unicode::encode(from_num, options.utf.unwrap_or(Utf::Utf8))
+            .into_iter()
+            .map(BigUint::from)
+            .collect()
+    } else if options.is_string {
+        let sep_list = [',', '.', ' ', '-', '_', sep_char];
+        let has_sep = from_num.contains(&sep_list[..]);
+
+        let group = 2 * options.byt_nr;
+        if !has_sep
+            && from_alphabet.base() == 16
+            && options.from_alphabet.is_none()
+            && from_num.len().is_multiple_of(group)
+        {
+            // No separators, manually split into bytes, or --byt-nr byte numbers
+            parse_hex_string(from_num, 16, group)?
+        } else {
+            // Gather each separated number into a vector for separate conversion
+            from_num
+                .split(&sep_list[..])
+                .map(|num| convert_to_base_10_with(num, from_alphabet, sep_char))
+                .collect::<Result<Vec<BigUint>, Error>>()?
+        }
+    } else if let Some(code) = options.from_code {
+        // The value of a bit pattern in a hardware code
+        let pattern = convert_to_base_10_with(from_num, from_alphabet, sep_char)?;
+        let value = match code.decode(&pattern) {
+            Ok(v) => v,
+            Err(e) => {
+                return Err(Error::new(
+                    ErrorCode::CodeErr,
+                    format!("Could not read {} as {}: {}", from_num, code.name(), e),
+                ));
+            }
+        };
+        input.set_signed(&value, width, options.signed_repr)?
+    } else if let Some(layout) = options
+        .layout
+        .as_ref()
+        .filter(|_| !options.field.is_empty())
+    {
+        // Set the given fields of the input number
+        let value = convert_to_base_10_with(from_num, from_alphabet, sep_char)?;
+        match layout.compose(&value, &options.field) {
+            Ok(v) => vec![v],
+            Err(e) => {
+                return Err(Error::new(
+                    ErrorCode::LayoutErr,
+                    format!("Error setting fields: {}", e),
+                ));
+            }
+        }
+    } else if let Some(adc) = options.adc.as_ref().filter(|_| options.scaled) {
+        // A voltage to the code nearest it
+        let value = match decimal::parse_decimal(&from_num.replace(sep_char, ""))
+            .filter(|_| decimal_input)
+        {
+            Some(v) => v,
+            None => {
+                return Err(Error::new(
+                    ErrorCode::AdcErr,
+                    format!("Could not read {} as a voltage", from_num),
+                ));
+            }
+        };
+        let quantized = adc.code(&value);
+        if quantized.clipped {
+            record.warnings.push(format!(
+                "{} V is out of the --adc range, clipped to code {}",
+                from_num, quantized.code
+            ));
+        }
+        input.adc_error = Some(quantized.error);
+        vec![quantized.code]
+    } else if let Some(preamble) = options.preamble.as_ref().filter(|_| options.scaled) {
+        // A scaled value back to the code it was measured as
+        let value = match decimal::parse_decimal(&from_num.replace(sep_char, ""))
+            .filter(|_| decimal_input)
+        {
+            Some(v) => v,
+            None => {
+                return Err(Error::new(
+                    ErrorCode::WaveformErr,
+                    format!(
+                        "Could not read {} as a decimal {} value",
+                        from_num, preamble.yunit
+                    ),
+                ));
+            }
+        };
+        input.set_signed(&preamble.code(&value), width, options.signed_repr)?
+    } else if let Some(fmt) = options.q_format {
+        // Decimal input is a real number to quantize, anything else is
+        // the raw integer
+        let pattern = if decimal_input {
+            let value = match decimal::parse_decimal(&from_num.replace(sep_char, "")) {
+                Some(v) => v,
+                None => {
+                    return Err(Error::new(
+                        ErrorCode::FixedPointErr,
+                        format!("Could not read {} as a real number", from_num),
+                    ));
+                }
+            };
+            let quantized = fixed::quantize(&value, fmt, options.rounding, options.overflow);
+            if quantized.overflowed {
+                let action = match options.overflow {
+                    Overflow::Saturate => "saturated to the nearest end",
+                    Overflow::Wrap => "wrapped around",
+                };
+                record.warnings.push(format!(
+                    "{} is out of range for {}, {}",
+                    from_num, fmt, action
+                ));
+            }
+            input.q_error = Some(quantized.error);
+            signed::encode(&quantized.raw, fmt.bits(), SignedRepr::TwosComplement)?
+        } else {
+            convert_to_base_10_with(from_num, from_alphabet, sep_char)?
+        };
+        if pattern.bits() > u64::from(fmt.bits()) {
+            return Err(Error::new(
+                ErrorCode::FixedPointErr,
+                format!("Raw value does not fit in {}", fmt),
+            ));
+        }
+        vec![pattern]
+    } else if let Some(fmt) = options.float {
+        // Decimal input is a float to encode, anything else is a bit pattern
+        let pattern = if decimal_input {
+            float::encode(&from_num.replace(sep_char, ""), fmt)?
+        } else {
+            convert_to_base_10_with(from_num, from_alphabet, sep_char)?
+        };
+        float::decode(&pattern, fmt)?;
+        vec![pattern]
+    } else if let Some(value) = prefixed {
+        if value.is_integer() {
+            input.set_signed(&value.to_integer(), width, options.signed_repr)?
+        } else {
+            input.set_fraction(value, width)?
+        }
+    } else if !options.expr
+        && from_num.contains('.')
+        && sep_char != '.'
+        && !from_alphabet.contains('.')
+    {
+        let value = fraction::convert_fraction_to_base_10(from_num, from_alphabet, sep_char)?;
+        input.set_fraction(value, width)?
+    } else {
+        let value = if options.expr {
+            let value = expr::evaluate(from_num, from_alphabet)?;
+            if let Some(width) = width {
+                expr::check_width(&value, width)?;
+            }
+            value
+        } else {
+            Number::parse_with(from_num, from_alphabet, sep_char)?
+                .value()
+                .clone()
+        };
+        // Negative --adc codes are two's complement ones
+        let width = width.or_else(|| {
+            options
+                .adc
+                .as_ref()
+                .filter(|a| a.coding == Coding::Twos)
+                .map(|a| a.bits)
+        });
+        input.set_signed(&value, width, options.signed_repr)?
+    };
+    Ok(nums)
+}
+
+impl Input {
+    /// The pattern of a signed value at `width` bits, or its magnitude
+    /// without a width
+    fn set_signed(
+        &mut self,
+        value: &BigInt,
+        width: Option<u32>,
+        repr: SignedRepr,
+    ) -> Result<Vec<BigUint>, Error> {
+        match width {
+            Some(width) => Ok(vec![signed::encode(value, width, repr)?]),
+            None => {
+                self.negative = value.is_negative();
+                Ok(vec![value.magnitude().clone()])
+            }
+        }
+    }
+
+    /// The whole part of a real value, which is kept for the targets
+    fn set_fraction(
+        &mut self,
+        value: BigRational,
+        width: Option<u32>,
+    ) -> Result<Vec<BigUint>, Error> {
+        if width.is_some() {
+            return Err(Error::new(
+                ErrorCode::WidthErr,
+                "Numbers with a fractional part can not be shown at a --width",
+            ));
+        }
+        let int_part = value.trunc().to_integer().magnitude().clone();
+        self.fraction = Some(value);
+        Ok(vec![int_part])
+    }
+
+    /// The value of a pattern, signed when it was given at a width
+    fn signed_value(
+        &self,
+        num: &BigUint,
+        width: Option<u32>,
+        repr: SignedRepr,
+    ) -> Result<BigInt, Error> {
+        match width {
+            Some(width) => signed::decode(num, width, repr),
+            None if self.negative => Ok(-BigInt::from(num.clone())),
+            None => Ok(BigInt::from(num.clone())),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   join
+//
+// NOTES:  The items of a string as one number, e.g. a magic number
+// ARGS:
+//     options - what to convert, and how
+//     input - the items
+//     order - order of the bytes in the number
+// RETURN: The number, at the full width of the items, or an error if an
+//         item is wider than one unit
+//
+fn join(options: &Options, input: Input, order: ByteOrder) -> Result<Input, Error> {
+    let unit_bits = options.utf.map_or(8, |u| u.unit_bits());
+    // A wider item would shift every byte after it
+    if let Some(num) = input.nums.iter().find(|n| n.bits() > u64::from(unit_bits)) {
+        return Err(Error::new(
+            ErrorCode::EncodingErr,
+            format!("{} does not fit in {} bits", num, unit_bits),
+        ));
+    }
+    let bytes = as_bytes(&input.nums, false, Some(unit_bits))?;
+    let joined = match endian::swap(&bytes, order, bytes.len()) {
+        Ok(v) => v,
+        Err(e) => {
+            return Err(Error::new(
+                ErrorCode::EncodingErr,
+                format!("Could not join the bytes of {}: {}", options.input, e),
+            ));
+        }
+    };
+    Ok(Input {
+        nums: vec![BigUint::from_bytes_be(&joined)],
+        // Leading zero bytes are kept, the number is shown at full width
+        pattern_width: Some(bytes.len() as u32 * 8),
+        is_string: false,
+        ..input
+    })
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   target_rows
+//
+// NOTES:  Converts the values to one target, with the function for that
+//         kind of target
+// ARGS:
+//     target - a base, an alphabet name, or a named target like Q
+//     options - what to convert, and how
+//     input - the input, as read
+//     nums - the values, in the byte order being shown
+//     alphabets - the alphabets of the targets, by name
+// RETURN: The rows of the target, or an error
+//
+fn target_rows(
+    target: &str,
+    options: &Options,
+    input: &Input,
+    nums: &[BigUint],
+    alphabets: &HashMap<String, Alphabet>,
+) -> Result<Vec<Row>, Error> {
+    let is_any = |names: &[&str]| names.iter().any(|n| target.eq_ignore_ascii_case(n));
+    let row = if is_any(&["L", "LAYOUT"]) {
+        return layout_rows(target, options, nums);
+    } else if is_any(&["A"]) {
+        text_row(options, input, nums)?
+    } else if let Ok(code) = target.parse::<Code>() {
+        code_row(code, options, input, nums)?
+    } else if is_any(&["CP", "NAME"]) {
+        unicode_row(target, options, input, nums)?
+    } else if is_any(&["ENG", "SI", "IEC"]) {
+        prefix_row(target, options, input, nums)
+    } else if is_any(&["V", "LSB", "VERR"]) {
+        adc_row(target, options, input, nums)?
+    } else if is_any(&["Y", "T"]) {
+        waveform_row(target, options, input, nums)?
+    } else if let Ok(enc) = target.parse::<Encoding>() {
+        encoding_row(enc, input, nums)?
+    } else if is_any(&["S", "U"]) {
+        integer_row(target, options, input, nums)?
+    } else if is_any(&["Q", "QERR"]) {
+        fixed_row(target, options, input, nums)?
+    } else if is_any(&["F", "FIELDS"]) {
+        float_row(target, options, nums)?
+    } else {
+        base_row(target, options, input, nums, alphabets)?
+    };
+    Ok(vec![row])
+}
+
+/// A row whose value has no separators
+fn row(base: impl Into<String>, value: String) -> Row {
+    Row {
+        base: base.into(),
+        raw: value.clone(),
+        value,
+    }
+}
+
+/// Each value converted by `f`, separated like the items of a string
+fn each<F>(options: &Options, nums: &[BigUint], f: F) -> Result<String, Error>
+where
+    F: FnMut(&BigUint) -> Result<String, Error>,
+{
+    Ok(nums
+        .iter()
+        .map(f)
+        .collect::<Result<Vec<String>, Error>>()?
+        .join(&options.separators.sep_char.to_string()))
+}
+
+/// What a target needs from the options, or an error saying so
+fn needs<T>(value: Option<T>, target: &str, what: &str) -> Result<T, Error> {
+    value.ok_or_else(|| {
+        Error::new(
+            ErrorCode::TargetBaseErr,
+            format!("Target {} needs {}", target, what),
+        )
+    })
+}
+
+/// One row per field of the register
+fn layout_rows(target: &str, options: &Options, nums: &[BigUint]) -> Result<Vec<Row>, Error> {
+    let layout = needs(options.layout.as_ref(), target, "a --layout")?;
+    let mut rows = Vec::new();
+    for num in nums.iter() {
+        for field in layout.decode(num) {
+            let mut out_str = format!("{} = {}", field.binary, field.value);
+            if let Some(name) = field.name {
+                out_str += &format!(" ({})", name);
+            }
+            rows.push(row(field.label, out_str));
+        }
+    }
+    Ok(rows)
+}
+
+/// The text of a string from its code units, of a number from its bytes,
+/// or the FourCC code of a number
+fn text_row(options: &Options, input: &Input, nums: &[BigUint]) -> Result<Row, Error> {
+    if options.fourcc {
+        // The code, and what it is if it is a known one
+        let code = fourcc::to_code(&nums[0], options.fourcc_size, options.fourcc_endian)
+            .map_err(|e| Error::new(ErrorCode::EncodingErr, e))?;
+        let out_str = match fourcc::describe(&code) {
+            Some(description) => format!("{} – {}", code, description),
+            None => code.clone(),
+        };
+        return Ok(Row {
+            base: fourcc::name(options.fourcc_size).to_uppercase(),
+            value: out_str,
+            raw: code,
+        });
+    }
+    let text = if input.is_string {
+        unicode::decode(nums, options.utf.unwrap_or(Utf::Utf8))
+    } else {
+        let bytes = as_bytes(nums, false, input.pattern_width)?;
+        unicode::decode(
+            &bytes
+                .into_iter()
+                .map(BigUint::from)
+                .collect::<Vec<BigUint>>(),
+            Utf::Utf8,
+        )
+    };
+    let text = match text {
+        Ok(v) => v,
+        Err(e) => {
+            return Err(Error::new(
+                ErrorCode::EncodingErr,
+                format!("Could not decode {} as text: {}", options.input, e),
+            ));
+        }
+    };
+    let label = match options.utf.filter(|_| input.is_string) {
+        Some(utf) => utf.name(),
+        None if text.is_ascii() => "ASCII",
+        None => Utf::Utf8.name(),
+    };
+    Ok(row(label, text))
+}
+
+/// The bits of each value in a hardware code, in whole digits
+fn code_row(code: Code, options: &Options, input: &Input, nums: &[BigUint]) -> Result<Row, Error> {
+    if input.fraction.is_some() {
+        return Err(Error::new(
+            ErrorCode::CodeErr,
+            format!("Only whole numbers have a {} code", code.name()),
+        ));
+    }
+    let separators = &options.separators;
+    let binary = Alphabet::standard(2).expect("base 2");
+    let mut out_strs = Vec::new();
+    let mut raws = Vec::new();
+    for num in nums.iter() {
+        let value = input.signed_value(num, options.width, options.signed_repr)?;
+        let pattern = code
+            .encode(&value)
+            .map_err(|e| Error::new(ErrorCode::CodeErr, e))?;
+        let mut digits = as_string_base_with(&pattern, &binary);
+        let len = match code.digit_bits() {
+            1 => input.pattern_width.unwrap_or(0) as usize,
+            bits => digits.len().div_ceil(bits as usize) * bits as usize,
+        };
+        while digits.len() < len {
+            digits.insert(0, '0');
+        }
+        out_strs.push(if options.no_sep {
+            digits.clone()
+        } else {
+            separators.separate(&digits, "2")
+        });
+        raws.push(digits);
+    }
+    let sep = separators.sep_char.to_string();
+    Ok(Row {
+        base: code.name(),
+        value: out_strs.join(&sep),
+        raw: raws.join(&sep),
+    })
+}
+
+/// Code points or names of the text of a string, or of each number
+fn unicode_row(
+    target: &str,
+    options: &Options,
+    input: &Input,
+    nums: &[BigUint],
+) -> Result<Row, Error> {
+    let chars: Vec<char> = if input.is_string {
+        match unicode::decode(nums, options.utf.unwrap_or(Utf::Utf8)) {
+            Ok(v) => v.chars().collect(),
+            Err(e) => {
+                return Err(Error::new(
+                    ErrorCode::EncodingErr,
+                    format!("Could not decode {} as text: {}", options.input, e),
+                ));
+            }
+        }
+    } else {
+        nums.iter()
+            .map(|num| {
+                num.to_u32().and_then(char::from_u32).ok_or_else(|| {
+                    Error::new(
+                        ErrorCode::EncodingErr,
+                        format!("{} is not a Unicode code point", num),
+                    )
+                })
+            })
+            .collect::<Result<Vec<char>, Error>>()?
+    };
+    if target.eq_ignore_ascii_case("CP") {
+        let points: Vec<String> = chars.iter().map(|&c| unicode::code_point(c)).collect();
+        Ok(row("Code points", points.join(" ")))
+    } else {
+        let names: Vec<String> = chars.iter().map(|&c| unicode::char_name(c)).collect();
+        Ok(row("Names", names.join(", ")))
+    }
+}
+
+/// Engineering notation, or with an SI or binary prefix
+fn prefix_row(target: &str, options: &Options, input: &Input, nums: &[BigUint]) -> Row {
+    let values: Vec<BigRational> = match &input.fraction {
+        Some(value) => vec![value.clone()],
+        None => nums
+            .iter()
+            .map(|num| {
+                let value = BigRational::from_integer(BigInt::from(num.clone()));
+                if input.negative {
+                    -value
+                } else {
+                    value
+                }
+            })
+            .collect(),
+    };
+    let (label, format): (&str, fn(&BigRational) -> String) = if target.eq_ignore_ascii_case("ENG")
+    {
+        ("Engineering", prefix::format_eng)
+    } else if target.eq_ignore_ascii_case("SI") {
+        ("SI", prefix::format_si)
+    } else {
+        ("IEC", prefix::format_iec)
+    };
+    let out_str = values
+        .iter()
+        .map(format)
+        .collect::<Vec<String>>()
+        .join(&options.separators.sep_char.to_string());
+    row(label, out_str)
+}
+
+/// Voltage of each code, the size of one, or the error made getting it
+fn adc_row(target: &str, options: &Options, input: &Input, nums: &[BigUint]) -> Result<Row, Error> {
+    let adc = needs(options.adc.as_ref(), target, "--adc")?;
+    if target.eq_ignore_ascii_case("V") {
+        if input.negative {
+            return Err(Error::new(
+                ErrorCode::AdcErr,
+                "Negative codes need --width, or --coding twos",
+            ));
+        }
+        let out_str = each(options, nums, |num| match adc.voltage(num) {
+            Ok(v) => Ok(decimal::format_significant(&v, 12)),
+            Err(e) => Err(Error::new(ErrorCode::AdcErr, e)),
+        })?;
+        Ok(row("Volts", out_str))
+    } else if target.eq_ignore_ascii_case("LSB") {
+        Ok(row("LSB", decimal::format_significant(&adc.lsb(), 12)))
+    } else {
+        let error = needs(
+            input.adc_error.as_ref(),
+            target,
+            "a --scaled voltage as input",
+        )?;
+        Ok(row("Error", adc.format_error(error)))
+    }
+}
+
+/// Scaled value of each code, or time of each sample
+fn waveform_row(
+    target: &str,
+    options: &Options,
+    input: &Input,
+    nums: &[BigUint],
+) -> Result<Row, Error> {
+    let preamble = needs(
+        options.preamble.as_ref(),
+        target,
+        "a --preamble or --ymult style options",
+    )?;
+    if target.eq_ignore_ascii_case("Y") {
+        // Block samples are signed as --bn-fmt says, --width patterns always are
+        let signed_codes = match &options.block {
+            Some(_) => options.bn_fmt == SampleFormat::Signed,
+            None => options.width.is_some(),
+        };
+        let width = input.pattern_width.filter(|_| signed_codes);
+        let out_str = each(options, nums, |num| {
+            let code = input.signed_value(num, width, options.signed_repr)?;
+            Ok(decimal::format_significant(&preamble.value(&code), 12))
+        })?;
+        Ok(row(format!("Y ({})", preamble.yunit), out_str))
+    } else {
+        let out_str = (0..nums.len())
+            .map(|idx| decimal::format_significant(&preamble.time(idx), 12))
+            .collect::<Vec<String>>()
+            .join(&options.separators.sep_char.to_string());
+        Ok(row(format!("T ({})", preamble.xunit), out_str))
+    }
+}
+
+/// The bytes of the values in a binary-to-text encoding
+fn encoding_row(enc: Encoding, input: &Input, nums: &[BigUint]) -> Result<Row, Error> {
+    if input.fraction.is_some() {
+        return Err(Error::new(
+            ErrorCode::EncodingErr,
+            format!("Only whole numbers can be shown as {}", enc.name()),
+        ));
+    }
+    if input.negative {
+        return Err(Error::new(
+            ErrorCode::EncodingErr,
+            "Negative numbers need a --width to be shown as bytes",
+        ));
+    }
+    let bytes = as_bytes(nums, input.is_string, input.pattern_width)?;
+    match encoding::encode(&bytes, enc) {
+        Ok(v) => Ok(row(enc.name(), v)),
+        Err(e) => Err(Error::new(
+            ErrorCode::EncodingErr,
+            format!("Could not encode as {}: {}", enc.name(), e),
+        )),
+    }
+}
+
+/// Signed or unsigned reading of the bit pattern at --width bits
+fn integer_row(
+    target: &str,
+    options: &Options,
+    input: &Input,
+    nums: &[BigUint],
+) -> Result<Row, Error> {
+    let width = needs(input.pattern_width, target, "a --width")?;
+    if target.eq_ignore_ascii_case("S") {
+        let out_str = each(options, nums, |num| {
+            signed::decode(num, width, options.signed_repr).map(|v| v.to_string())
+        })?;
+        Ok(row("Signed", out_str))
+    } else {
+        let out_str = each(options, nums, |num| Ok(num.to_string()))?;
+        Ok(row("Unsigned", out_str))
+    }
+}
+
+/// Real value of the raw integer, or the error made getting it
+fn fixed_row(
+    target: &str,
+    options: &Options,
+    input: &Input,
+    nums: &[BigUint],
+) -> Result<Row, Error> {
+    let fmt = needs(options.q_format, target, "a --q format")?;
+    if target.eq_ignore_ascii_case("Q") {
+        let out_str = each(options, nums, |num| {
+            let raw = if fmt.signed {
+                signed::decode(num, fmt.bits(), SignedRepr::TwosComplement)?
+            } else {
+                num.clone().into()
+            };
+            Ok(fixed::format_real(&fixed::to_real(&raw, fmt), fmt))
+        })?;
+        Ok(row(fmt.to_string(), out_str))
+    } else {
+        let error = needs(input.q_error.as_ref(), target, "a real number as input")?;
+        Ok(row("Error", fixed::format_error(error, fmt)))
+    }
+}
+
+/// Float value or field breakdown of the bit pattern
+fn float_row(target: &str, options: &Options, nums: &[BigUint]) -> Result<Row, Error> {
+    let fmt = needs(options.float, target, "a --float format")?;
+    let fields = target.eq_ignore_ascii_case("FIELDS");
+    let out_str = each(options, nums, |num| {
+        let decoded = float::decode(num, fmt)?;
+        if fields {
+            Ok(float::format_fields(&decoded, fmt))
+        } else {
+            Ok(float::format_value(&decoded, fmt))
+        }
+    })?;
+    if fields {
+        Ok(row("Fields", out_str))
+    } else {
+        Ok(row(fmt.name, out_str))
+    }
+}
+
+/// The digits of the values in a base or an alphabet
+fn base_row(
+    target: &str,
+    options: &Options,
+    input: &Input,
+    nums: &[BigUint],
+    alphabets: &HashMap<String, Alphabet>,
+) -> Result<Row, Error> {
+    let separators = &options.separators;
+    let sep_char = separators.sep_char;
+    let alphabet = match alphabets
+        .get(target)
+        .cloned()
+        .or_else(|| Alphabet::named(target))
+    {
+        Some(v) => v,
+        None => match target.parse::<u32>() {
+            Ok(v) => match Alphabet::standard(v) {
+                Ok(v) => v,
+                Err(e) => {
+                    return Err(Error::new(
+                        ErrorCode::InputBaseErr,
+                        format!("Error with custom base: {}", e),
+                    ));
+                }
+            },
+            Err(_) => {
+                return Err(Error::new(ErrorCode::TargetBaseErr, format!(
+                        "Error with target base {}\nPlease provide target base is base 10, or an alphabet name.",
+                        target
+                    )));
+            }
+        },
+    };
+    let zero_digit = alphabet.digit_char(0);
+
+    // Get the separator length for this base
+    let sep_length = separators.length(target);
+
+    if let Some(value) = &input.fraction {
+        let digits = fraction::as_fraction_digits(value, &alphabet, options.frac_digits);
+        let sep_length = if options.no_sep { 0 } else { sep_length };
+        return Ok(Row {
+            base: alphabet.name,
+            value: digits.to_separated_string(sep_length, sep_char),
+            raw: digits.to_separated_string(0, sep_char),
+        });
+    }
+
+    let mut out_str = String::from("");
+    let mut raw = String::from("");
+    for num in nums.iter() {
+        let mut this_num_str = as_string_base_with(num, &alphabet);
+
+        // Show the whole bit pattern when a width was given
+        if let Some(width) = input.pattern_width {
+            if let Some(digits) = signed::pattern_digits(width, alphabet.base()) {
+                while this_num_str.chars().count() < digits {
+                    this_num_str.insert(0, zero_digit);
+                }
+            }
+        }
+
+        //
+        // Pad the print string with separator characters if needed
+        //
+        if input.is_string || options.adc.is_some() {
+            this_num_str = separators.pad(&this_num_str, target, zero_digit);
+        }
+        // Keep the items of a string apart, so raw can be read back
+        if !raw.is_empty() {
+            raw.push(sep_char);
+        }
+        if input.negative {
+            raw.push('-');
+        }
+        raw.push_str(&this_num_str);
+
+        if input.is_string {
+            this_num_str.push(sep_char);
+        } else if !options.no_sep {
+            this_num_str = separators.separate(&this_num_str, target);
+        }
+
+        // Append to final out string
+        if input.negative {
+            out_str.push('-');
+        }
+        out_str.push_str(&this_num_str);
+    }
+    if input.is_string {
+        out_str.pop();
+    }
+    Ok(Row {
+        base: alphabet.name,
+        value: out_str,
+        raw,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(input: &str) -> Options {
+        Options {
+            input: input.to_string(),
+            ..Options::default()
+        }
+    }
+
+    fn rows(record: &Record) -> Vec<(&str, &str)> {
+        record
+            .rows
+            .iter()
+            .map(|row| (row.base.as_str(), row.value.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_convert() {
+        let mut record = Record::default();
+        let value = convert(&options("255"), &mut record).unwrap();
+        assert_eq!(value, Some(Number::from(255i64)));
+        assert_eq!(
+            rows(&record),
+            vec![("2", "1111 1111"), ("10", "255"), ("16", "FF")]
+        );
+
+        let mut record = Record::default();
+        let string = Options {
+            from_base: 16,
+            is_string: true,
+            targets: vec![String::from("A"), String::from("10")],
+            ..options("4142")
+        };
+        assert_eq!(convert(&string, &mut record), Ok(None));
+        assert_eq!(rows(&record), vec![("ASCII", "AB"), ("10", "65 66")]);
+        assert_eq!(record.rows[1].raw, "65 66");
+
+        // Prefixes only when asked for
+        assert!(convert(&options("4k7"), &mut Record::default()).is_err());
+        let prefixed = Options {
+            prefixes: true,
+            ..options("4k7")
+        };
+        let value = convert(&prefixed, &mut Record::default()).unwrap();
+        assert_eq!(value, Some(Number::from(4700i64)));
+    }
+
+    #[test]
+    fn test_errors_and_warnings() {
+        // Rows before the bad target are kept
+        let mut record = Record::default();
+        let opts = Options {
+            targets: vec![String::from("16"), String::from("Q")],
+            ..options("255")
+        };
+        let err = convert(&opts, &mut record).unwrap_err();
+        assert_eq!(err.code, ErrorCode::TargetBaseErr);
+        assert_eq!(rows(&record), vec![("16", "FF")]);
+
+        let mut record = Record::default();
+        let opts = Options {
+            q_format: "Q1.15".parse().ok(),
+            ..options("2")
+        };
+        assert!(convert(&opts, &mut record).is_ok());
+        assert_eq!(record.warnings.len(), 1);
+
+        let opts = Options {
+            scaled: true,
+            ..options("1")
+        };
+        let err = convert(&opts, &mut Record::default()).unwrap_err();
+        assert_eq!(err.code, ErrorCode::WaveformErr);
+
+        // Byte encodings have no place for the fraction
+        let opts = Options {
+            targets: vec![String::from("b64")],
+            ..options("1.5")
+        };
+        let err = convert(&opts, &mut Record::default()).unwrap_err();
+        assert_eq!(err.code, ErrorCode::EncodingErr);
+    }
+
+    #[test]
+    fn test_join() {
+        let joined = |input: &str, order: ByteOrder, target: &str| {
+            let opts = Options {
+                is_string: true,
+                join: Some(order),
+                targets: vec![target.to_string()],
+                ..options(input)
+            };
+            let mut record = Record::default();
+            convert(&opts, &mut record).map(|_| rows(&record)[0].1.to_string())
+        };
+        assert_eq!(joined("1 2", ByteOrder::Big, "16").unwrap(), "0102");
+        assert_eq!(joined("1 2", ByteOrder::Little, "16").unwrap(), "0201");
+        // Leading zero bytes are kept
+        assert_eq!(joined("0 1", ByteOrder::Big, "16").unwrap(), "0001");
+        assert_eq!(
+            joined("0 1", ByteOrder::Big, "2").unwrap(),
+            "0000 0000 0000 0001"
+        );
+        // Every item has to be one unit wide
+        let err = joined("1 300", ByteOrder::Big, "16").unwrap_err();
+        assert_eq!(err.code, ErrorCode::EncodingErr);
+    }
+}
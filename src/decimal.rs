@@ -0,0 +1,195 @@
+////////////////////////////////////////////////////////////////////////////////
+//  Module:   decimal.rs
+//
+//  Copyright © 2019 Zachary Nielsen
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+////////////////////////////////////////////////////////////////////////////////
+
+////////////////////////////////////////////////////////////////////////////////
+//  Namespaces
+////////////////////////////////////////////////////////////////////////////////
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{One, Signed, Zero};
+
+////////////////////////////////////////////////////////////////////////////////
+//  CODE
+////////////////////////////////////////////////////////////////////////////////
+
+/// Largest exponent read, past the range of f128 but small enough that
+/// the power of 10 is quick to build
+const MAX_EXPONENT: i32 = 5000;
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   parse_decimal
+//
+// NOTES:
+//     Reads a decimal real number exactly, e.g. "-12.5", ".25", or "1.5e-3".
+//     Keeping it as a ratio means nothing is lost before it is rounded to
+//     whatever format it is headed for.
+// ARGS:
+//     text - the number to parse
+// RETURN: The exact value, or None if `text` is not a decimal number or
+//         its exponent is past MAX_EXPONENT either way
+//
+pub fn parse_decimal(text: &str) -> Option<BigRational> {
+    let (negative, rest) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+
+    let (mantissa, exponent) = match rest.find(['e', 'E']) {
+        Some(idx) => (
+            &rest[..idx],
+            rest[idx + 1..]
+                .parse::<i32>()
+                .ok()
+                .filter(|e| e.abs() <= MAX_EXPONENT)?,
+        ),
+        None => (rest, 0),
+    };
+
+    let (int_part, frac_part) = match mantissa.find('.') {
+        Some(idx) => (&mantissa[..idx], &mantissa[idx + 1..]),
+        None => (mantissa, ""),
+    };
+
+    let all_digits = |s: &str| s.chars().all(|c| c.is_ascii_digit());
+    if int_part.len() + frac_part.len() == 0 || !all_digits(int_part) || !all_digits(frac_part) {
+        return None;
+    }
+
+    let digits: BigInt = format!("0{}{}", int_part, frac_part).parse().ok()?;
+    let scale = exponent.checked_sub(frac_part.len() as i32)?;
+    let value = BigRational::from_integer(digits) * pow10(scale);
+
+    Some(if negative { -value } else { value })
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   format_significant
+//
+// NOTES:
+//     Writes `value` with at most `digits` significant digits, rounding the
+//     last one half away from zero.  Trailing zeros are dropped, and very
+//     large or small numbers switch to scientific notation.
+// ARGS:
+//     value - the number to write
+//     digits - the maximum number of significant digits (at least 1)
+// RETURN: The number as a string
+//
+pub fn format_significant(value: &BigRational, digits: usize) -> String {
+    if value.is_zero() {
+        return String::from("0");
+    }
+
+    let digits = digits.max(1) as i32;
+    let magnitude = value.abs();
+
+    let mut exp10 = exponent(&magnitude);
+
+    let mut rounded = (&magnitude * pow10(digits - 1 - exp10))
+        .round()
+        .to_integer();
+    if rounded >= pow10(digits).to_integer() {
+        // Rounding carried into a new leading digit, e.g. 9.99 -> 10.0
+        exp10 += 1;
+        rounded = (&magnitude * pow10(digits - 1 - exp10))
+            .round()
+            .to_integer();
+    }
+
+    let all = rounded.to_string();
+    let sig = all.trim_end_matches('0');
+    let sig = if sig.is_empty() { "0" } else { sig };
+
+    let body = if (-5..21).contains(&exp10) {
+        if exp10 >= 0 {
+            let int_len = exp10 as usize + 1;
+            if sig.len() <= int_len {
+                format!("{}{}", sig, "0".repeat(int_len - sig.len()))
+            } else {
+                format!("{}.{}", &sig[..int_len], &sig[int_len..])
+            }
+        } else {
+            format!("0.{}{}", "0".repeat((-exp10 - 1) as usize), sig)
+        }
+    } else if sig.len() > 1 {
+        format!("{}.{}e{}", &sig[..1], &sig[1..], exp10)
+    } else {
+        format!("{}e{}", sig, exp10)
+    };
+
+    if value.is_negative() {
+        format!("-{}", body)
+    } else {
+        body
+    }
+}
+
+/// The decimal exponent of the leading digit of a positive number, e.g. 2
+/// for 123.4
+pub fn exponent(magnitude: &BigRational) -> i32 {
+    let mut exp10 = ((magnitude.numer().bits() as f64 - magnitude.denom().bits() as f64)
+        * std::f64::consts::LOG10_2) as i32;
+    while pow10(exp10) > *magnitude {
+        exp10 -= 1;
+    }
+    while pow10(exp10 + 1) <= *magnitude {
+        exp10 += 1;
+    }
+    exp10
+}
+
+/// 10 raised to `exp`, as an exact ratio
+pub fn pow10(exp: i32) -> BigRational {
+    BigRational::from_integer(BigInt::from(10)).pow(exp)
+}
+
+/// 2 raised to `exp`, as an exact ratio
+pub fn pow2(exp: i64) -> BigRational {
+    let shifted = BigInt::one() << exp.unsigned_abs();
+    if exp >= 0 {
+        BigRational::from_integer(shifted)
+    } else {
+        BigRational::new(BigInt::one(), shifted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ratio(numer: i64, denom: i64) -> BigRational {
+        BigRational::new(BigInt::from(numer), BigInt::from(denom))
+    }
+
+    #[test]
+    fn test_parse_decimal() {
+        assert_eq!(parse_decimal("12.5"), Some(ratio(25, 2)));
+        assert_eq!(parse_decimal("-.25"), Some(ratio(-1, 4)));
+        assert_eq!(parse_decimal("1.5e-3"), Some(ratio(3, 2000)));
+        assert_eq!(parse_decimal("+2E2"), Some(ratio(200, 1)));
+        assert_eq!(parse_decimal("."), None);
+        assert_eq!(parse_decimal("1.2.3"), None);
+        assert_eq!(parse_decimal("0x10"), None);
+        assert!(parse_decimal("1e-4966").is_some());
+        assert_eq!(parse_decimal("1e99999999"), None);
+        assert_eq!(parse_decimal("1e-5001"), None);
+    }
+
+    #[test]
+    fn test_format_significant() {
+        assert_eq!(format_significant(&ratio(1, 10), 9), "0.1");
+        assert_eq!(format_significant(&ratio(-25, 2), 9), "-12.5");
+        assert_eq!(format_significant(&ratio(2, 3), 4), "0.6667");
+        assert_eq!(format_significant(&ratio(9999, 1), 2), "10000");
+        assert_eq!(format_significant(&ratio(1, 1_000_000), 3), "1e-6");
+        assert_eq!(format_significant(&pow2(100), 5), "1.2677e30");
+    }
+}
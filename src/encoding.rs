@@ -0,0 +1,373 @@
+////////////////////////////////////////////////////////////////////////////////
+//  Module:   encoding.rs
+//
+//  Copyright © 2019 Zachary Nielsen
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+////////////////////////////////////////////////////////////////////////////////
+
+////////////////////////////////////////////////////////////////////////////////
+//  Namespaces
+////////////////////////////////////////////////////////////////////////////////
+use crate::alphabet::Alphabet;
+use num_bigint::BigUint;
+use std::str::FromStr;
+
+////////////////////////////////////////////////////////////////////////////////
+//  CODE
+////////////////////////////////////////////////////////////////////////////////
+
+const BASE64_DIGITS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const BASE64URL_DIGITS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+const BASE32_DIGITS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+const Z85_DIGITS: &[u8] =
+    b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ.-:+=^!/*?&<>()[]{}@%$#";
+
+/// A text encoding of a byte string
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encoding {
+    /// RFC 4648 base64, padded
+    Base64,
+    /// RFC 4648 URL safe base64, unpadded
+    Base64Url,
+    /// RFC 4648 base32, padded
+    Base32,
+    /// Bitcoin base58, leading zero bytes kept as '1's
+    Base58,
+    /// Adobe/btoa ascii85, without the <~ ~> delimiters
+    Ascii85,
+    /// ZeroMQ Z85, for byte strings a multiple of 4 long
+    Z85,
+}
+
+impl Encoding {
+    /// Name of the encoding, for the output row
+    pub fn name(&self) -> &'static str {
+        match self {
+            Encoding::Base64 => "Base64",
+            Encoding::Base64Url => "Base64url",
+            Encoding::Base32 => "Base32",
+            Encoding::Base58 => "Base58",
+            Encoding::Ascii85 => "Ascii85",
+            Encoding::Z85 => "Z85",
+        }
+    }
+}
+
+impl FromStr for Encoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "b64" => Ok(Encoding::Base64),
+            "b64url" => Ok(Encoding::Base64Url),
+            "b32" => Ok(Encoding::Base32),
+            "b58" => Ok(Encoding::Base58),
+            "a85" | "ascii85" => Ok(Encoding::Ascii85),
+            "z85" => Ok(Encoding::Z85),
+            _ => Err(format!(
+                "Unknown encoding '{}'. Use b64, b64url, b32, b58, a85, or z85",
+                s
+            )),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   encode
+//
+// NOTES:  Writes a byte string in `encoding`
+// ARGS:
+//     bytes - the bytes to encode
+//     encoding - the text encoding to use
+// RETURN: The encoded text, or an error if `bytes` can not be encoded
+//
+pub fn encode(bytes: &[u8], encoding: Encoding) -> Result<String, String> {
+    match encoding {
+        Encoding::Base64 => Ok(encode_bits(bytes, BASE64_DIGITS, 6, true)),
+        Encoding::Base64Url => Ok(encode_bits(bytes, BASE64URL_DIGITS, 6, false)),
+        Encoding::Base32 => Ok(encode_bits(bytes, BASE32_DIGITS, 5, true)),
+        Encoding::Base58 => Ok(encode_base58(bytes)),
+        Encoding::Ascii85 => Ok(encode_85(bytes, 33, true)),
+        Encoding::Z85 => {
+            if !bytes.len().is_multiple_of(4) {
+                return Err(format!(
+                    "Z85 needs a multiple of 4 bytes, got {}",
+                    bytes.len()
+                ));
+            }
+            Ok(encode_85(bytes, 0, false))
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   decode
+//
+// NOTES:
+//     Reads text in `encoding` back to bytes.  Padding is optional for the
+//     base64 and base32 flavours, and whitespace is ignored.
+// ARGS:
+//     text - the encoded text
+//     encoding - the text encoding it is in
+// RETURN: The bytes, or an error describing the bad input
+//
+pub fn decode(text: &str, encoding: Encoding) -> Result<Vec<u8>, String> {
+    let text: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+    match encoding {
+        Encoding::Base64 => decode_bits(&text, BASE64_DIGITS, 6, false),
+        Encoding::Base64Url => decode_bits(&text, BASE64URL_DIGITS, 6, false),
+        Encoding::Base32 => decode_bits(&text, BASE32_DIGITS, 5, true),
+        Encoding::Base58 => decode_base58(&text),
+        Encoding::Ascii85 => {
+            let text = text.strip_prefix("<~").unwrap_or(&text);
+            let text = text.strip_suffix("~>").unwrap_or(text);
+            decode_85(text, 33, true)
+        }
+        Encoding::Z85 => {
+            if !text.len().is_multiple_of(5) {
+                return Err(format!(
+                    "Z85 needs a multiple of 5 chars, got {}",
+                    text.len()
+                ));
+            }
+            decode_85(&text, 0, false)
+        }
+    }
+}
+
+/// Packs bytes into `bits` bit digits, for base64 and base32
+fn encode_bits(bytes: &[u8], digits: &[u8], bits: u32, pad: bool) -> String {
+    let mut out = String::new();
+    let mut buffer: u32 = 0;
+    let mut buffered = 0;
+    for &byte in bytes {
+        buffer = (buffer << 8) | u32::from(byte);
+        buffered += 8;
+        while buffered >= bits {
+            buffered -= bits;
+            out.push(digits[((buffer >> buffered) & ((1 << bits) - 1)) as usize] as char);
+        }
+    }
+    if buffered > 0 {
+        out.push(digits[((buffer << (bits - buffered)) & ((1 << bits) - 1)) as usize] as char);
+    }
+
+    if pad {
+        // Pad to a whole number of byte/digit groups: 3/4 for base64, 5/8 for base32
+        let group = if bits == 6 { 4 } else { 8 };
+        while !out.len().is_multiple_of(group) {
+            out.push('=');
+        }
+    }
+    out
+}
+
+/// Unpacks `bits` bit digits back into bytes, for base64 and base32
+fn decode_bits(
+    text: &str,
+    digits: &[u8],
+    bits: u32,
+    case_insensitive: bool,
+) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    let mut buffer: u32 = 0;
+    let mut buffered = 0;
+    for c in text.trim_end_matches('=').chars() {
+        let value = digits
+            .iter()
+            .position(|&d| {
+                if case_insensitive {
+                    (d as char).eq_ignore_ascii_case(&c)
+                } else {
+                    d as char == c
+                }
+            })
+            .ok_or_else(|| format!("'{}' is not a valid digit", c))?;
+        buffer = (buffer << bits) | value as u32;
+        buffered += bits;
+        if buffered >= 8 {
+            buffered -= 8;
+            out.push((buffer >> buffered) as u8);
+        }
+    }
+    // Whatever is left over must be zero fill, and less than a whole digit
+    if buffered >= bits || buffer & ((1 << buffered) - 1) != 0 {
+        return Err(String::from("Input ends part way through a byte"));
+    }
+    Ok(out)
+}
+
+/// Base58 is a plain base conversion, except each leading zero byte is kept
+/// as a leading zero digit
+fn encode_base58(bytes: &[u8]) -> String {
+    let alphabet = Alphabet::named("base58").expect("base58 is a named alphabet");
+    let zeros = bytes.iter().take_while(|&&b| b == 0).count();
+    let mut out: String = std::iter::repeat_n(alphabet.digit_char(0), zeros).collect();
+    if zeros < bytes.len() {
+        let num = BigUint::from_bytes_be(&bytes[zeros..]);
+        out.extend(
+            num.to_radix_be(alphabet.base())
+                .iter()
+                .map(|&d| alphabet.digit_char(d)),
+        );
+    }
+    out
+}
+
+fn decode_base58(text: &str) -> Result<Vec<u8>, String> {
+    let alphabet = Alphabet::named("base58").expect("base58 is a named alphabet");
+    let digits = text
+        .chars()
+        .map(|c| {
+            alphabet
+                .digit_value(c)
+                .ok_or_else(|| format!("'{}' is not a valid digit", c))
+        })
+        .collect::<Result<Vec<u8>, String>>()?;
+    let zeros = digits.iter().take_while(|&&d| d == 0).count();
+    let mut out = vec![0; zeros];
+    if zeros < digits.len() {
+        let num =
+            BigUint::from_radix_be(&digits[zeros..], alphabet.base()).expect("digits are in range");
+        out.extend(num.to_bytes_be());
+    }
+    Ok(out)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   encode_85
+//
+// NOTES:
+//     Each 4 bytes become 5 base 85 digits, most significant first.  A short
+//     last group is zero filled, and only as many digits as it needs kept.
+// ARGS:
+//     bytes - the bytes to encode
+//     offset - char of digit 0 for ascii85, or 0 to use the Z85 digits
+//     zero_group - write an all zero group as 'z' (ascii85)
+// RETURN: The encoded text
+//
+fn encode_85(bytes: &[u8], offset: u8, zero_group: bool) -> String {
+    let mut out = String::new();
+    for chunk in bytes.chunks(4) {
+        let mut group = [0u8; 4];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let mut value = u32::from_be_bytes(group);
+        if zero_group && value == 0 && chunk.len() == 4 {
+            out.push('z');
+            continue;
+        }
+
+        let mut digits = [0u8; 5];
+        for digit in digits.iter_mut().rev() {
+            *digit = (value % 85) as u8;
+            value /= 85;
+        }
+        for &digit in &digits[..chunk.len() + 1] {
+            out.push(digit_85(digit, offset));
+        }
+    }
+    out
+}
+
+fn decode_85(text: &str, offset: u8, zero_group: bool) -> Result<Vec<u8>, String> {
+    let mut values = Vec::new();
+    for c in text.chars() {
+        if zero_group && c == 'z' {
+            if values.len() % 5 != 0 {
+                return Err(String::from("'z' in the middle of a group"));
+            }
+            values.extend_from_slice(&[0; 5]);
+            continue;
+        }
+        let value = if offset > 0 {
+            (c as u32)
+                .checked_sub(u32::from(offset))
+                .filter(|&v| v < 85)
+        } else {
+            Z85_DIGITS
+                .iter()
+                .position(|&d| d as char == c)
+                .map(|v| v as u32)
+        };
+        values.push(value.ok_or_else(|| format!("'{}' is not a valid digit", c))?);
+    }
+    if values.len() % 5 == 1 {
+        return Err(String::from("Input ends part way through a byte"));
+    }
+
+    let mut out = Vec::new();
+    for chunk in values.chunks(5) {
+        // A short last group was cut from a zero filled one, so fill it
+        // with the highest digit to get the same leading bytes back
+        let mut group = [84u32; 5];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let value = group
+            .iter()
+            .try_fold(0u32, |acc, &d| acc.checked_mul(85)?.checked_add(d))
+            .ok_or_else(|| String::from("Group is too large for 4 bytes"))?;
+        out.extend_from_slice(&value.to_be_bytes()[..chunk.len() - 1]);
+    }
+    Ok(out)
+}
+
+fn digit_85(digit: u8, offset: u8) -> char {
+    if offset > 0 {
+        (offset + digit) as char
+    } else {
+        Z85_DIGITS[digit as usize] as char
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(bytes: &[u8], encoding: Encoding, text: &str) {
+        assert_eq!(encode(bytes, encoding), Ok(text.to_string()));
+        assert_eq!(decode(text, encoding), Ok(bytes.to_vec()));
+    }
+
+    #[test]
+    fn test_base64() {
+        round_trip(b"", Encoding::Base64, "");
+        round_trip(b"f", Encoding::Base64, "Zg==");
+        round_trip(b"foob", Encoding::Base64, "Zm9vYg==");
+        round_trip(b"foobar", Encoding::Base64, "Zm9vYmFy");
+        round_trip(&[0xFB, 0xFF], Encoding::Base64Url, "-_8");
+        assert_eq!(decode("Zm9vYg", Encoding::Base64), Ok(b"foob".to_vec()));
+        assert!(decode("Zm9vYh", Encoding::Base64).is_err());
+        assert!(decode("Zm$v", Encoding::Base64).is_err());
+    }
+
+    #[test]
+    fn test_base32() {
+        round_trip(b"fo", Encoding::Base32, "MZXQ====");
+        round_trip(b"foobar", Encoding::Base32, "MZXW6YTBOI======");
+        assert_eq!(decode("mzxq", Encoding::Base32), Ok(b"fo".to_vec()));
+    }
+
+    #[test]
+    fn test_base58() {
+        round_trip(b"hello world", Encoding::Base58, "StV1DL6CwTryKyV");
+        round_trip(&[0, 0, 0x28, 0x7F], Encoding::Base58, "1145k");
+        assert!(decode("0OIl", Encoding::Base58).is_err());
+    }
+
+    #[test]
+    fn test_85() {
+        round_trip(b"Man ", Encoding::Ascii85, "9jqo^");
+        round_trip(b"sure.", Encoding::Ascii85, "F*2M7/c");
+        round_trip(&[0, 0, 0, 0, 1], Encoding::Ascii85, "z!<");
+        assert_eq!(decode("<~9jqo^~>", Encoding::Ascii85), Ok(b"Man ".to_vec()));
+
+        let hello = [0x86, 0x4F, 0xD2, 0x6F, 0xB5, 0x59, 0xF7, 0x5B];
+        round_trip(&hello, Encoding::Z85, "HelloWorld");
+        assert!(encode(b"abc", Encoding::Z85).is_err());
+        assert!(decode("Hell", Encoding::Z85).is_err());
+    }
+}
@@ -0,0 +1,156 @@
+////////////////////////////////////////////////////////////////////////////////
+//  Module:   endian.rs
+//
+//  Copyright © 2019 Zachary Nielsen
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+////////////////////////////////////////////////////////////////////////////////
+
+////////////////////////////////////////////////////////////////////////////////
+//  Namespaces
+////////////////////////////////////////////////////////////////////////////////
+use std::str::FromStr;
+
+////////////////////////////////////////////////////////////////////////////////
+//  CODE
+////////////////////////////////////////////////////////////////////////////////
+
+/// Order of the bytes of a multi-byte value, relative to big endian.  Every
+/// swap is its own inverse, so the same view reads a value off the wire or
+/// lays one out for it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ByteOrder {
+    Big,
+    Little,
+    /// 16-bit words in reverse order, bytes within a word kept (0x0C0D0A0B
+    /// for 0x0A0B0C0D), as Modbus devices often send 32-bit values
+    WordSwap,
+    /// Bytes swapped within each 16-bit word (0x0B0A0D0C for 0x0A0B0C0D),
+    /// the PDP-11 middle endian layout
+    ByteSwap,
+}
+
+impl ByteOrder {
+    /// Short label for the output rows
+    pub fn label(&self) -> &'static str {
+        match self {
+            ByteOrder::Big => "BE",
+            ByteOrder::Little => "LE",
+            ByteOrder::WordSwap => "WS",
+            ByteOrder::ByteSwap => "PDP",
+        }
+    }
+
+    /// Number of bytes the swap works on at once
+    fn unit(&self) -> usize {
+        match self {
+            ByteOrder::Big | ByteOrder::Little => 1,
+            ByteOrder::WordSwap | ByteOrder::ByteSwap => 2,
+        }
+    }
+}
+
+impl FromStr for ByteOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "big" | "be" | "msb" => Ok(ByteOrder::Big),
+            "little" | "le" | "lsb" => Ok(ByteOrder::Little),
+            "word" | "ws" | "word-swap" => Ok(ByteOrder::WordSwap),
+            "pdp" | "middle" | "byte-swap" => Ok(ByteOrder::ByteSwap),
+            _ => Err(format!(
+                "Unknown byte order '{}'. Use big, little, word, or pdp",
+                s
+            )),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   swap
+//
+// NOTES:
+//     Reorders `bytes` one `chunk_len` byte value at a time.  Big endian
+//     input is the identity.
+// ARGS:
+//     bytes - the bytes, most significant first
+//     order - byte order to swap to
+//     chunk_len - bytes per value
+// RETURN: The swapped bytes, or an error if they do not split into values
+//
+pub fn swap(bytes: &[u8], order: ByteOrder, chunk_len: usize) -> Result<Vec<u8>, String> {
+    if chunk_len == 0 || !bytes.len().is_multiple_of(chunk_len) {
+        return Err(format!(
+            "{} bytes do not split into {} byte values",
+            bytes.len(),
+            chunk_len
+        ));
+    }
+    if !chunk_len.is_multiple_of(order.unit()) {
+        return Err(format!(
+            "{} swaps need a whole number of 16-bit words, got {} bytes",
+            order.label(),
+            chunk_len
+        ));
+    }
+
+    let mut out = Vec::with_capacity(bytes.len());
+    for chunk in bytes.chunks(chunk_len) {
+        match order {
+            ByteOrder::Big => out.extend_from_slice(chunk),
+            ByteOrder::Little => out.extend(chunk.iter().rev()),
+            ByteOrder::WordSwap => {
+                for word in chunk.rchunks(2) {
+                    out.extend_from_slice(word);
+                }
+            }
+            ByteOrder::ByteSwap => {
+                for word in chunk.chunks(2) {
+                    out.extend(word.iter().rev());
+                }
+            }
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALUE: [u8; 4] = [0x0A, 0x0B, 0x0C, 0x0D];
+
+    #[test]
+    fn test_swap() {
+        assert_eq!(swap(&VALUE, ByteOrder::Big, 4), Ok(VALUE.to_vec()));
+        assert_eq!(
+            swap(&VALUE, ByteOrder::Little, 4),
+            Ok(vec![0x0D, 0x0C, 0x0B, 0x0A])
+        );
+        assert_eq!(
+            swap(&VALUE, ByteOrder::WordSwap, 4),
+            Ok(vec![0x0C, 0x0D, 0x0A, 0x0B])
+        );
+        assert_eq!(
+            swap(&VALUE, ByteOrder::ByteSwap, 4),
+            Ok(vec![0x0B, 0x0A, 0x0D, 0x0C])
+        );
+        assert_eq!(
+            swap(&VALUE, ByteOrder::Little, 2),
+            Ok(vec![0x0B, 0x0A, 0x0D, 0x0C])
+        );
+    }
+
+    #[test]
+    fn test_swap_errors() {
+        assert!(swap(&VALUE, ByteOrder::Little, 3).is_err());
+        assert!(swap(&VALUE[..3], ByteOrder::WordSwap, 3).is_err());
+        assert_eq!("LE".parse(), Ok(ByteOrder::Little));
+        assert!("sideways".parse::<ByteOrder>().is_err());
+    }
+}
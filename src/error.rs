@@ -0,0 +1,301 @@
+////////////////////////////////////////////////////////////////////////////////
+//  Module:   error.rs
+//
+//  Copyright © 2019 Zachary Nielsen
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+////////////////////////////////////////////////////////////////////////////////
+
+////////////////////////////////////////////////////////////////////////////////
+//  Namespaces
+////////////////////////////////////////////////////////////////////////////////
+use crate::alphabet::Alphabet;
+use std::fmt;
+
+////////////////////////////////////////////////////////////////////////////////
+//  CODE
+////////////////////////////////////////////////////////////////////////////////
+
+/// Category of an error, which sets the exit code
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorCode {
+    BaseConversionErr,
+    TargetBaseErr,
+    InputBaseErr,
+    SeparatorMapParseError,
+    ClipboardErr,
+    WidthErr,
+    FloatErr,
+    FixedPointErr,
+    EncodingErr,
+    LayoutErr,
+    ExpressionErr,
+    OverflowErr,
+    FormatErr,
+    BatchErr,
+    ConfigErr,
+    BlockErr,
+    WaveformErr,
+    AdcErr,
+    CodeErr,
+}
+
+impl ErrorCode {
+    /// Exit code of the command line tool.  1 is left for other failures,
+    /// like bad arguments.
+    pub fn exit_code(&self) -> i32 {
+        match *self {
+            ErrorCode::BaseConversionErr => 2,
+            ErrorCode::TargetBaseErr => 3,
+            ErrorCode::InputBaseErr => 4,
+            ErrorCode::SeparatorMapParseError => 5,
+            ErrorCode::ClipboardErr => 6,
+            ErrorCode::WidthErr => 7,
+            ErrorCode::FloatErr => 8,
+            ErrorCode::FixedPointErr => 9,
+            ErrorCode::EncodingErr => 10,
+            ErrorCode::LayoutErr => 11,
+            ErrorCode::ExpressionErr => 12,
+            ErrorCode::OverflowErr => 13,
+            ErrorCode::FormatErr => 14,
+            ErrorCode::BatchErr => 15,
+            ErrorCode::ConfigErr => 16,
+            ErrorCode::BlockErr => 17,
+            ErrorCode::WaveformErr => 18,
+            ErrorCode::AdcErr => 19,
+            ErrorCode::CodeErr => 20,
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                ErrorCode::BaseConversionErr => "Base Conversion Error",
+                ErrorCode::TargetBaseErr => "Target Base Error",
+                ErrorCode::InputBaseErr => "Input Base Error",
+                ErrorCode::ClipboardErr => "Clipboard access Error",
+                ErrorCode::SeparatorMapParseError => "Separator Map Parse Error",
+                ErrorCode::WidthErr => "Width Error",
+                ErrorCode::FloatErr => "Float Error",
+                ErrorCode::FixedPointErr => "Fixed Point Error",
+                ErrorCode::EncodingErr => "Encoding Error",
+                ErrorCode::LayoutErr => "Layout Error",
+                ErrorCode::ExpressionErr => "Expression Error",
+                ErrorCode::OverflowErr => "Overflow Error",
+                ErrorCode::FormatErr => "Format Error",
+                ErrorCode::BatchErr => "Batch Input Error",
+                ErrorCode::ConfigErr => "Config Error",
+                ErrorCode::BlockErr => "Binary Block Error",
+                ErrorCode::WaveformErr => "Waveform Preamble Error",
+                ErrorCode::AdcErr => "ADC Error",
+                ErrorCode::CodeErr => "Binary Code Error",
+            }
+        )
+    }
+}
+
+/// An error, with what is known about the input that caused it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Error {
+    pub code: ErrorCode,
+    /// What went wrong, one line
+    pub message: String,
+    /// The text that could not be read
+    pub input: Option<String>,
+    /// Char index of the first bad char in `input`
+    pub position: Option<usize>,
+    /// The base or alphabet that was tried
+    pub base: Option<String>,
+    /// A likely fix
+    pub suggestion: Option<String>,
+}
+
+impl Error {
+    pub fn new<S: Into<String>>(code: ErrorCode, message: S) -> Error {
+        Error {
+            code,
+            message: message.into(),
+            input: None,
+            position: None,
+            base: None,
+            suggestion: None,
+        }
+    }
+
+    pub fn with_input<S: Into<String>>(mut self, input: S) -> Error {
+        self.input = Some(input.into());
+        self
+    }
+
+    pub fn with_position(mut self, position: usize) -> Error {
+        self.position = Some(position);
+        self
+    }
+
+    pub fn with_base<S: Into<String>>(mut self, base: S) -> Error {
+        self.base = Some(base.into());
+        self
+    }
+
+    pub fn with_suggestion<S: Into<String>>(mut self, suggestion: S) -> Error {
+        self.suggestion = Some(suggestion.into());
+        self
+    }
+
+    /// Makes the error about `input`, when it was found `offset` chars into
+    /// it, e.g. in the digits after a sign
+    pub fn within(mut self, input: &str, offset: usize) -> Error {
+        self.position = self.position.map(|p| p + offset);
+        self.input = Some(input.to_string());
+        self
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        self.code.exit_code()
+    }
+}
+
+/// The message, then the input with a caret under the bad char, then the
+/// suggestion
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        if let (Some(input), Some(position)) = (&self.input, self.position) {
+            write!(f, "\n    {}\n    {}^", input, " ".repeat(position))?;
+        }
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, "\n  help: {}", suggestion)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// How a base is named in messages, "base 16" or "base58"
+pub fn base_label(alphabet: &Alphabet) -> String {
+    if alphabet.name.parse::<u32>().is_ok() {
+        format!("base {}", alphabet.name)
+    } else {
+        alphabet.name.clone()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   digit_error
+//
+// NOTES:
+//     Builds the error for `text` not being a number in `alphabet`.  Points
+//     at the first char that is not a digit, and suggests a fix when one is
+//     likely: a bigger base for a digit that is too big, the other case in
+//     case sensitive alphabets, or --sep-char for punctuation.
+// ARGS:
+//     text - the number as given
+//     alphabet - digits of the base that was tried
+//     sep_char - separator char, skipped
+//     fraction - a '-' sign and one radix point are allowed
+// RETURN: The error
+//
+pub fn digit_error(text: &str, alphabet: &Alphabet, sep_char: char, fraction: bool) -> Error {
+    let base = base_label(alphabet);
+    let mut error = Error::new(
+        ErrorCode::BaseConversionErr,
+        format!("Could not convert {} from {}", text, base),
+    )
+    .with_input(text)
+    .with_base(alphabet.name.clone());
+
+    let mut seen_point = false;
+    let bad = text.chars().enumerate().find(|&(idx, c)| {
+        let sign = idx == 0 && (c == '+' || (fraction && c == '-')) && !alphabet.contains(c);
+        let point = fraction && c == '.' && !seen_point && !alphabet.contains('.');
+        seen_point |= point;
+        !(sign || point || c == sep_char || alphabet.digit_value(c).is_some())
+    });
+    let (position, c) = match bad {
+        Some(v) => v,
+        // Every char is fine, so there were no digits
+        None => return error,
+    };
+
+    error.message = format!("'{}' is not a {} digit", c, base);
+    error = error.with_position(position);
+
+    let other_case = if c.is_lowercase() {
+        c.to_uppercase().next()
+    } else {
+        c.to_lowercase().next()
+    };
+    let standard = alphabet.name.parse::<u32>().ok();
+    if let Some(value) = c.to_digit(36).filter(|_| standard.is_some_and(|b| b <= 36)) {
+        error.with_suggestion(format!("did you mean base {}+?", value + 1))
+    } else if let Some(other) = other_case.filter(|&o| o != c && alphabet.digit_value(o).is_some())
+    {
+        error.with_suggestion(format!(
+            "{} is case sensitive, did you mean '{}'?",
+            alphabet.name, other
+        ))
+    } else if c.is_ascii_punctuation() || c.is_whitespace() {
+        error.with_suggestion(format!("use --sep-char '{}' to skip it", c))
+    } else {
+        error
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_digit_error() {
+        let hex = Alphabet::standard(16).unwrap();
+        let err = digit_error("+1G_F", &hex, '_', false);
+        assert_eq!(err.code, ErrorCode::BaseConversionErr);
+        assert_eq!(err.message, "'G' is not a base 16 digit");
+        assert_eq!(err.position, Some(2));
+        assert_eq!(err.base, Some(String::from("16")));
+        assert_eq!(err.suggestion, Some(String::from("did you mean base 17+?")));
+        assert_eq!(
+            err.to_string(),
+            "'G' is not a base 16 digit\n    +1G_F\n      ^\n  help: did you mean base 17+?"
+        );
+
+        let err = digit_error("1,000", &Alphabet::standard(10).unwrap(), ' ', false);
+        assert_eq!(err.position, Some(1));
+        assert_eq!(
+            err.suggestion,
+            Some(String::from("use --sep-char ',' to skip it"))
+        );
+
+        let base58 = Alphabet::named("base58").unwrap();
+        let err = digit_error("3Yr0", &base58, ' ', false);
+        assert_eq!(err.position, Some(3));
+        assert_eq!(err.suggestion, None);
+        let err = digit_error("-1.5.1", &Alphabet::standard(10).unwrap(), ' ', true);
+        assert_eq!(err.position, Some(4));
+
+        // No digits at all, nothing to point at
+        let err = digit_error("+", &hex, ' ', false);
+        assert_eq!(err.position, None);
+        assert_eq!(err.message, "Could not convert + from base 16");
+    }
+
+    #[test]
+    fn test_within() {
+        let err = Error::new(ErrorCode::BaseConversionErr, "bad")
+            .with_input("FG")
+            .with_position(1)
+            .within("-FG", 1);
+        assert_eq!(err.input, Some(String::from("-FG")));
+        assert_eq!(err.position, Some(2));
+        assert_eq!(err.exit_code(), 2);
+    }
+}
@@ -0,0 +1,406 @@
+////////////////////////////////////////////////////////////////////////////////
+//  Module:   expr.rs
+//
+//  Copyright © 2019 Zachary Nielsen
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+////////////////////////////////////////////////////////////////////////////////
+
+////////////////////////////////////////////////////////////////////////////////
+//  Namespaces
+////////////////////////////////////////////////////////////////////////////////
+use crate::{alphabet::Alphabet, convert_to_base_10_with, Error, ErrorCode};
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use std::convert::TryFrom;
+
+////////////////////////////////////////////////////////////////////////////////
+//  CODE
+////////////////////////////////////////////////////////////////////////////////
+
+/// Largest intermediate result, in bits.  Anything past this is reported as
+/// an overflow rather than left to run the machine out of memory.
+pub const MAX_BITS: u64 = 1 << 20;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(BigInt),
+    Op(&'static str),
+    Open,
+    Close,
+}
+
+/// Operators, longest first so "<<" is not read as "<"
+const OPERATORS: &[&str] = &[
+    "**", "<<", ">>", "+", "-", "*", "/", "%", "&", "|", "^", "~",
+];
+
+/// Binary operators by precedence, loosest first, C style
+const PRECEDENCE: &[&[&str]] = &[
+    &["|"],
+    &["^"],
+    &["&"],
+    &["<<", ">>"],
+    &["+", "-"],
+    &["*", "/", "%"],
+];
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   evaluate
+//
+// NOTES:
+//     Evaluates an integer expression, e.g. "0x1F << 4 | 0b101".  Literals
+//     without a 0x, 0o, or 0b prefix are read in `alphabet`.  A prefix is
+//     only a prefix when its letter is not a digit of `alphabet`, so hex
+//     0B1 is still 0xB1.  Bitwise operators act on the two's complement of
+//     negative numbers, as if they had infinitely many sign bits.
+// ARGS:
+//     text - the expression
+//     alphabet - digits of unprefixed literals
+// RETURN: The value, or an error for bad syntax or an overflow
+//
+pub fn evaluate(text: &str, alphabet: &Alphabet) -> Result<BigInt, Error> {
+    let tokens = tokenize(text, alphabet)?;
+    let mut parser = Parser { tokens, pos: 0 };
+    let value = parser.binary(0)?;
+    match parser.tokens.get(parser.pos) {
+        None => Ok(value),
+        Some(token) => Err(Error::new(
+            ErrorCode::ExpressionErr,
+            format!("Unexpected {} in expression {}", describe(token), text),
+        )),
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   check_width
+//
+// NOTES:
+//     Checks a result fits a `width` bit pattern, as either a signed or an
+//     unsigned number.
+// ARGS:
+//     value - the result
+//     width - bits it has to fit in
+// RETURN: Nothing, or an overflow error
+//
+pub fn check_width(value: &BigInt, width: u32) -> Result<(), Error> {
+    let limit = BigInt::one() << width;
+    let min = -(&limit >> 1usize);
+    if *value < min || *value >= limit {
+        return Err(Error::new(
+            ErrorCode::OverflowErr,
+            format!("{} overflows {} bits", value, width),
+        ));
+    }
+    Ok(())
+}
+
+fn tokenize(text: &str, alphabet: &Alphabet) -> Result<Vec<Token>, Error> {
+    let mut tokens = Vec::new();
+    let mut rest = text.trim_start();
+    while !rest.is_empty() {
+        if let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(*op)) {
+            tokens.push(Token::Op(op));
+            rest = &rest[op.len()..];
+        } else if let Some(after) = rest.strip_prefix('(') {
+            tokens.push(Token::Open);
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix(')') {
+            tokens.push(Token::Close);
+            rest = after;
+        } else {
+            let end = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            if end == 0 {
+                let position = text[..text.len() - rest.len()].chars().count();
+                return Err(Error::new(
+                    ErrorCode::ExpressionErr,
+                    format!(
+                        "Unexpected '{}' in expression",
+                        rest.chars().next().unwrap_or(' ')
+                    ),
+                )
+                .with_input(text)
+                .with_position(position));
+            }
+            tokens.push(Token::Num(literal(&rest[..end], alphabet)?));
+            rest = &rest[end..];
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+/// Reads one literal, with an optional 0x, 0o, or 0b prefix
+fn literal(text: &str, alphabet: &Alphabet) -> Result<BigInt, Error> {
+    let mut chars = text.chars();
+    if let (Some('0'), Some(letter)) = (chars.next(), chars.next()) {
+        let base = match letter.to_ascii_lowercase() {
+            'x' => 16,
+            'o' => 8,
+            'b' => 2,
+            _ => 0,
+        };
+        if base != 0 && alphabet.digit_value(letter).is_none() {
+            let prefixed = Alphabet::standard(base).expect("2, 8, and 16 are valid bases");
+            return Ok(convert_to_base_10_with(&text[2..], &prefixed, '_')?.into());
+        }
+    }
+    Ok(convert_to_base_10_with(text, alphabet, '_')?.into())
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Num(v) => format!("number {}", v),
+        Token::Op(op) => format!("'{}'", op),
+        Token::Open => String::from("'('"),
+        Token::Close => String::from("')'"),
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next_op(&self, ops: &[&str]) -> Option<&'static str> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Op(op)) if ops.contains(op) => Some(op),
+            _ => None,
+        }
+    }
+
+    /// Binary operators at `level` of PRECEDENCE and tighter, left to right
+    fn binary(&mut self, level: usize) -> Result<BigInt, Error> {
+        if level == PRECEDENCE.len() {
+            return self.unary();
+        }
+        let mut value = self.binary(level + 1)?;
+        while let Some(op) = self.next_op(PRECEDENCE[level]) {
+            self.pos += 1;
+            let rhs = self.binary(level + 1)?;
+            value = apply(op, value, rhs)?;
+        }
+        Ok(value)
+    }
+
+    fn unary(&mut self) -> Result<BigInt, Error> {
+        match self.next_op(&["-", "+", "~"]) {
+            Some(op) => {
+                self.pos += 1;
+                let value = self.unary()?;
+                Ok(match op {
+                    "-" => -value,
+                    "~" => !value,
+                    _ => value,
+                })
+            }
+            None => self.power(),
+        }
+    }
+
+    /// `**` binds tighter than unary minus on its left, and is right
+    /// associative: -2**2 is -4, 2**3**2 is 2**9
+    fn power(&mut self) -> Result<BigInt, Error> {
+        let base = self.primary()?;
+        if self.next_op(&["**"]).is_some() {
+            self.pos += 1;
+            let exp = self.unary()?;
+            return apply("**", base, exp);
+        }
+        Ok(base)
+    }
+
+    fn primary(&mut self) -> Result<BigInt, Error> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        match token {
+            Some(Token::Num(v)) => Ok(v),
+            Some(Token::Open) => {
+                let value = self.binary(0)?;
+                if self.tokens.get(self.pos) != Some(&Token::Close) {
+                    return Err(Error::new(
+                        ErrorCode::ExpressionErr,
+                        "Missing ')' in expression",
+                    ));
+                }
+                self.pos += 1;
+                Ok(value)
+            }
+            Some(other) => Err(Error::new(
+                ErrorCode::ExpressionErr,
+                format!("Expected a number, got {}", describe(&other)),
+            )),
+            None => Err(Error::new(
+                ErrorCode::ExpressionErr,
+                "Expression ends too soon",
+            )),
+        }
+    }
+}
+
+/// Applies a binary operator, catching results that would be too large
+fn apply(op: &str, lhs: BigInt, rhs: BigInt) -> Result<BigInt, Error> {
+    let overflow = || {
+        Err(Error::new(
+            ErrorCode::OverflowErr,
+            format!("{} {} {} overflows {} bits", lhs, op, rhs, MAX_BITS),
+        ))
+    };
+    let count = |what: &str| match rhs.to_u64() {
+        Some(v) => Ok(v),
+        None => Err(Error::new(
+            ErrorCode::ExpressionErr,
+            format!("{} must be a non-negative number, got {}", what, rhs),
+        )),
+    };
+
+    let value = match op {
+        "+" => &lhs + &rhs,
+        "-" => &lhs - &rhs,
+        "*" => &lhs * &rhs,
+        "/" | "%" if rhs.is_zero() => {
+            return Err(Error::new(ErrorCode::ExpressionErr, "Division by zero"));
+        }
+        // Truncating division, as in C
+        "/" => &lhs / &rhs,
+        "%" => &lhs % &rhs,
+        "&" => &lhs & &rhs,
+        "|" => &lhs | &rhs,
+        "^" => &lhs ^ &rhs,
+        "<<" => {
+            let shift = count("Shift")?;
+            if lhs.bits() + shift > MAX_BITS {
+                return overflow();
+            }
+            &lhs << shift
+        }
+        // Floors for negative numbers, like an arithmetic shift
+        ">>" => match count("Shift")? {
+            shift if shift >= lhs.bits() => {
+                if lhs.is_negative() {
+                    -BigInt::one()
+                } else {
+                    BigInt::zero()
+                }
+            }
+            shift => &lhs >> shift,
+        },
+        "**" => {
+            let exp = count("Exponent")?;
+            let magnitude = lhs.abs();
+            if magnitude > BigInt::one() && (magnitude.bits() - 1).saturating_mul(exp) > MAX_BITS {
+                return overflow();
+            }
+            // Every value of magnitude 0 or 1 raised to a power stays put,
+            // apart from the sign of -1
+            match u32::try_from(exp) {
+                Ok(exp) => lhs.pow(exp),
+                Err(_) if lhs == -BigInt::one() && exp.is_odd() => lhs.clone(),
+                Err(_) if magnitude.is_zero() => BigInt::zero(),
+                Err(_) => BigInt::one(),
+            }
+        }
+        _ => unreachable!("unknown operator {}", op),
+    };
+
+    if value.bits() > MAX_BITS {
+        return overflow();
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(text: &str) -> Result<BigInt, Error> {
+        evaluate(text, &Alphabet::standard(10).unwrap())
+    }
+
+    fn int(v: i64) -> Result<BigInt, Error> {
+        Ok(BigInt::from(v))
+    }
+
+    #[test]
+    fn test_examples() {
+        assert_eq!(eval("0x1F << 4 | 0b101"), int(0x1F5));
+        assert_eq!(eval("(1<<20)-1"), int(0xF_FFFF));
+        assert_eq!(eval("~0 & 0xFFFF"), int(0xFFFF));
+        assert_eq!(eval("0o777 % 13"), int(4));
+    }
+
+    #[test]
+    fn test_precedence() {
+        assert_eq!(eval("1 + 2 * 3"), int(7));
+        assert_eq!(eval("1 << 2 + 1"), int(8));
+        assert_eq!(eval("6 & 3 | 8 ^ 1"), int(11));
+        assert_eq!(eval("-2**2"), int(-4));
+        assert_eq!(eval("2**3**2"), int(512));
+        assert_eq!(eval("-7 / 2"), int(-3));
+        assert_eq!(eval("-7 >> 1"), int(-4));
+        assert_eq!(eval("-1 >> 100"), int(-1));
+        assert_eq!(eval("1_000 - 0x_10"), int(984));
+    }
+
+    #[test]
+    fn test_bases() {
+        let hex = Alphabet::standard(16).unwrap();
+        assert_eq!(evaluate("FF + 1", &hex), int(0x100));
+        assert_eq!(evaluate("0B1", &hex), int(0xB1));
+        assert_eq!(evaluate("0x10 * 10", &hex), int(0x100));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            eval("1 / 0").map_err(|e| e.code),
+            Err(ErrorCode::ExpressionErr)
+        );
+        assert_eq!(
+            eval("(1 + 2").map_err(|e| e.code),
+            Err(ErrorCode::ExpressionErr)
+        );
+        assert_eq!(
+            eval("1 +").map_err(|e| e.code),
+            Err(ErrorCode::ExpressionErr)
+        );
+        assert_eq!(
+            eval("1 2").map_err(|e| e.code),
+            Err(ErrorCode::ExpressionErr)
+        );
+        assert_eq!(eval("1 + #2").unwrap_err().position, Some(4));
+        assert!(eval("4k7 + 1").is_err());
+        assert_eq!(
+            eval("1 << -1").map_err(|e| e.code),
+            Err(ErrorCode::ExpressionErr)
+        );
+        assert_eq!(
+            eval("0xZZ").map_err(|e| e.code),
+            Err(ErrorCode::BaseConversionErr)
+        );
+        assert_eq!(
+            eval("1 << 0x100000").map_err(|e| e.code),
+            Err(ErrorCode::OverflowErr)
+        );
+        assert_eq!(
+            eval("3 ** 1000000").map_err(|e| e.code),
+            Err(ErrorCode::OverflowErr)
+        );
+        assert_eq!(eval("1 ** 99999999999"), int(1));
+
+        assert_eq!(check_width(&BigInt::from(-128), 8), Ok(()));
+        assert_eq!(check_width(&BigInt::from(255), 8), Ok(()));
+        assert_eq!(
+            check_width(&BigInt::from(256), 8).map_err(|e| e.code),
+            Err(ErrorCode::OverflowErr)
+        );
+    }
+}
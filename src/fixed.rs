@@ -0,0 +1,322 @@
+////////////////////////////////////////////////////////////////////////////////
+//  Module:   fixed.rs
+//
+//  Copyright © 2019 Zachary Nielsen
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+////////////////////////////////////////////////////////////////////////////////
+
+////////////////////////////////////////////////////////////////////////////////
+//  Namespaces
+////////////////////////////////////////////////////////////////////////////////
+use crate::{
+    decimal::{format_significant, pow2},
+    expr::MAX_BITS,
+};
+use num_bigint::BigInt;
+use num_integer::Integer;
+use num_rational::BigRational;
+use num_traits::{One, Signed, Zero};
+use std::str::FromStr;
+
+////////////////////////////////////////////////////////////////////////////////
+//  CODE
+////////////////////////////////////////////////////////////////////////////////
+
+/// A Qm.n or UQm.n fixed point format.  Following the ARM convention, the
+/// sign bit of a signed format is counted in `int_bits`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QFormat {
+    pub signed: bool,
+    pub int_bits: u32,
+    pub frac_bits: u32,
+}
+
+impl QFormat {
+    /// Total number of bits in the raw integer
+    pub fn bits(&self) -> u32 {
+        self.int_bits + self.frac_bits
+    }
+
+    /// Smallest and largest raw integer the format can hold
+    fn raw_range(&self) -> (BigInt, BigInt) {
+        if self.signed {
+            let half = BigInt::one() << (self.bits() - 1);
+            (-half.clone(), half - 1)
+        } else {
+            (BigInt::zero(), (BigInt::one() << self.bits()) - 1)
+        }
+    }
+}
+
+impl std::fmt::Display for QFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let prefix = if self.signed { "Q" } else { "UQ" };
+        write!(f, "{}{}.{}", prefix, self.int_bits, self.frac_bits)
+    }
+}
+
+impl FromStr for QFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.to_ascii_lowercase();
+        let (signed, rest) = if let Some(rest) = lower.strip_prefix("uq") {
+            (false, rest)
+        } else if let Some(rest) = lower.strip_prefix('u') {
+            (false, rest)
+        } else {
+            (true, lower.strip_prefix('q').unwrap_or(&lower))
+        };
+
+        let err = || format!("Could not read Q format '{}'. Use m.n, Qm.n, or UQm.n", s);
+        let (int_bits, frac_bits) = match rest.split_once('.') {
+            Some((m, n)) => (m.parse().map_err(|_| err())?, n.parse().map_err(|_| err())?),
+            // Qn is shorthand for a format with only the sign bit as integer part
+            None => (signed as u32, rest.parse().map_err(|_| err())?),
+        };
+
+        if signed && int_bits == 0 {
+            return Err(format!("{} has no room for a sign bit", s));
+        }
+        match int_bits.checked_add(frac_bits) {
+            Some(0) => return Err(format!("{} has no bits", s)),
+            Some(bits) if u64::from(bits) <= MAX_BITS => {}
+            _ => return Err(format!("{} is wider than {} bits", s, MAX_BITS)),
+        }
+        Ok(QFormat {
+            signed,
+            int_bits,
+            frac_bits,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rounding {
+    /// Round half away from zero
+    Nearest,
+    /// Round half to even
+    Even,
+    Floor,
+    Ceil,
+    /// Truncate toward zero
+    Zero,
+}
+
+impl FromStr for Rounding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "nearest" => Ok(Rounding::Nearest),
+            "even" => Ok(Rounding::Even),
+            "floor" => Ok(Rounding::Floor),
+            "ceil" => Ok(Rounding::Ceil),
+            "zero" | "trunc" => Ok(Rounding::Zero),
+            _ => Err(format!(
+                "Unknown rounding '{}'. Use nearest, even, floor, ceil, or zero",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Overflow {
+    Saturate,
+    Wrap,
+}
+
+impl FromStr for Overflow {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "saturate" | "sat" => Ok(Overflow::Saturate),
+            "wrap" => Ok(Overflow::Wrap),
+            _ => Err(format!("Unknown overflow '{}'. Use saturate or wrap", s)),
+        }
+    }
+}
+
+/// Result of quantizing a real number
+#[derive(Debug, PartialEq)]
+pub struct Quantized {
+    /// The raw integer, signed for Q formats
+    pub raw: BigInt,
+    /// Set when the value was out of range and got saturated or wrapped
+    pub overflowed: bool,
+    /// Quantized value minus the input value
+    pub error: BigRational,
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   quantize
+//
+// NOTES:  Rounds a real number to the nearest raw integer of `fmt`
+// ARGS:
+//     value - the real number
+//     fmt - the fixed point format
+//     rounding - how to round to a whole LSB
+//     overflow - what to do with values out of range
+// RETURN: The raw integer, and how far it is from `value`
+//
+pub fn quantize(
+    value: &BigRational,
+    fmt: QFormat,
+    rounding: Rounding,
+    overflow: Overflow,
+) -> Quantized {
+    let scaled = value * pow2(i64::from(fmt.frac_bits));
+    let rounded = match rounding {
+        Rounding::Nearest => scaled.round(),
+        Rounding::Floor => scaled.floor(),
+        Rounding::Ceil => scaled.ceil(),
+        Rounding::Zero => scaled.trunc(),
+        Rounding::Even => {
+            let floor = scaled.floor();
+            let remainder = &scaled - &floor;
+            let half = BigRational::new(BigInt::one(), BigInt::from(2));
+            if remainder > half || (remainder == half && floor.to_integer().is_odd()) {
+                floor + BigRational::one()
+            } else {
+                floor
+            }
+        }
+    }
+    .to_integer();
+
+    let (min, max) = fmt.raw_range();
+    let overflowed = rounded < min || rounded > max;
+    let raw = if !overflowed {
+        rounded
+    } else {
+        match overflow {
+            Overflow::Saturate if rounded < min => min,
+            Overflow::Saturate => max,
+            Overflow::Wrap => {
+                // Keep the low bits, then read them back in range
+                let modulus = BigInt::one() << fmt.bits();
+                let wrapped = rounded.mod_floor(&modulus);
+                if wrapped > max {
+                    wrapped - modulus
+                } else {
+                    wrapped
+                }
+            }
+        }
+    };
+
+    let error = to_real(&raw, fmt) - value;
+    Quantized {
+        raw,
+        overflowed,
+        error,
+    }
+}
+
+/// Real value of a raw integer in `fmt`
+pub fn to_real(raw: &BigInt, fmt: QFormat) -> BigRational {
+    BigRational::from_integer(raw.clone()) * pow2(-i64::from(fmt.frac_bits))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   format_real
+//
+// NOTES:
+//     Writes a value of `fmt` exactly.  Every value is a multiple of 2^-n,
+//     so it always ends within n decimal places.
+// ARGS:
+//     value - the real number
+//     fmt - the fixed point format it came from
+// RETURN: The value as a string
+//
+pub fn format_real(value: &BigRational, fmt: QFormat) -> String {
+    format_significant(value, (fmt.bits() + 1) as usize)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   format_error
+//
+// NOTES:  Writes a quantization error, also as a fraction of one LSB
+// ARGS:
+//     error - quantized value minus input value
+//     fmt - the fixed point format
+// RETURN: The error as a string
+//
+pub fn format_error(error: &BigRational, fmt: QFormat) -> String {
+    let lsbs = error * pow2(i64::from(fmt.frac_bits));
+    let sign = if error.is_negative() { "" } else { "+" };
+    format!(
+        "{}{} ({}{} LSB)",
+        sign,
+        format_significant(error, 12),
+        sign,
+        format_significant(&lsbs, 6)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decimal::parse_decimal;
+
+    fn q(text: &str) -> QFormat {
+        text.parse().unwrap()
+    }
+
+    fn quantize_str(text: &str, fmt: &str, rounding: Rounding, overflow: Overflow) -> i64 {
+        let value = parse_decimal(text).unwrap();
+        let raw = quantize(&value, q(fmt), rounding, overflow).raw;
+        raw.to_string().parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!(q("Q1.15").bits(), 16);
+        assert_eq!(q("q15"), q("1.15"));
+        assert_eq!(q("UQ8.8").to_string(), "UQ8.8");
+        assert_eq!(q("u16").bits(), 16);
+        assert!("Q0.15".parse::<QFormat>().is_err());
+        assert!("Qx.y".parse::<QFormat>().is_err());
+        assert!("Q4294967295.1".parse::<QFormat>().is_err());
+        assert!("UQ1048576.1".parse::<QFormat>().is_err());
+    }
+
+    #[test]
+    fn test_quantize() {
+        let (nearest, sat, wrap) = (Rounding::Nearest, Overflow::Saturate, Overflow::Wrap);
+        assert_eq!(quantize_str("0.5", "Q1.15", nearest, sat), 0x4000);
+        assert_eq!(quantize_str("-1", "Q1.15", nearest, sat), -0x8000);
+        assert_eq!(quantize_str("1", "Q1.15", nearest, sat), 0x7FFF);
+        assert_eq!(quantize_str("1", "Q1.15", nearest, wrap), -0x8000);
+        assert_eq!(quantize_str("-0.5", "UQ8.8", nearest, sat), 0);
+        assert_eq!(quantize_str("2.5", "UQ8.0", Rounding::Even, sat), 2);
+        assert_eq!(quantize_str("2.5", "UQ8.0", nearest, sat), 3);
+        assert_eq!(quantize_str("-2.5", "Q8.0", Rounding::Floor, sat), -3);
+        assert_eq!(quantize_str("-2.5", "Q8.0", Rounding::Zero, sat), -2);
+        assert_eq!(quantize_str("2.1", "Q8.0", Rounding::Ceil, sat), 3);
+    }
+
+    #[test]
+    fn test_error_and_format() {
+        let fmt = q("Q1.15");
+        let value = parse_decimal("0.1").unwrap();
+        let quantized = quantize(&value, fmt, Rounding::Nearest, Overflow::Saturate);
+        assert_eq!(quantized.raw, BigInt::from(3277));
+        assert!(!quantized.overflowed);
+        assert_eq!(
+            format_real(&to_real(&quantized.raw, fmt), fmt),
+            "0.100006103515625"
+        );
+        assert_eq!(
+            format_error(&quantized.error, fmt),
+            "+6.103515625e-6 (+0.2 LSB)"
+        );
+    }
+}
@@ -0,0 +1,401 @@
+////////////////////////////////////////////////////////////////////////////////
+//  Module:   float.rs
+//
+//  Copyright © 2019 Zachary Nielsen
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+////////////////////////////////////////////////////////////////////////////////
+
+////////////////////////////////////////////////////////////////////////////////
+//  Namespaces
+////////////////////////////////////////////////////////////////////////////////
+use crate::{
+    decimal::{format_significant, parse_decimal, pow2},
+    Error, ErrorCode,
+};
+use num_bigint::{BigInt, BigUint};
+use num_rational::BigRational;
+use num_traits::{One, ToPrimitive, Zero};
+use std::str::FromStr;
+
+////////////////////////////////////////////////////////////////////////////////
+//  CODE
+////////////////////////////////////////////////////////////////////////////////
+
+/// Layout of an IEEE-754 binary interchange format
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FloatFormat {
+    pub name: &'static str,
+    pub exp_bits: u32,
+    pub man_bits: u32,
+}
+
+pub const F16: FloatFormat = FloatFormat {
+    name: "f16",
+    exp_bits: 5,
+    man_bits: 10,
+};
+pub const BF16: FloatFormat = FloatFormat {
+    name: "bf16",
+    exp_bits: 8,
+    man_bits: 7,
+};
+pub const F32: FloatFormat = FloatFormat {
+    name: "f32",
+    exp_bits: 8,
+    man_bits: 23,
+};
+pub const F64: FloatFormat = FloatFormat {
+    name: "f64",
+    exp_bits: 11,
+    man_bits: 52,
+};
+pub const F128: FloatFormat = FloatFormat {
+    name: "f128",
+    exp_bits: 15,
+    man_bits: 112,
+};
+
+impl FloatFormat {
+    /// Total number of bits in the format
+    pub fn bits(&self) -> u32 {
+        1 + self.exp_bits + self.man_bits
+    }
+
+    fn bias(&self) -> i64 {
+        (1 << (self.exp_bits - 1)) - 1
+    }
+
+    fn max_biased_exp(&self) -> u64 {
+        (1 << self.exp_bits) - 1
+    }
+
+    /// Decimal digits needed to round trip any value of the format
+    fn max_digits(&self) -> usize {
+        ((self.man_bits + 1) as f64 * std::f64::consts::LOG10_2).ceil() as usize + 1
+    }
+}
+
+impl FromStr for FloatFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "f16" | "half" => Ok(F16),
+            "bf16" => Ok(BF16),
+            "f32" | "single" => Ok(F32),
+            "f64" | "double" => Ok(F64),
+            "f128" | "quad" => Ok(F128),
+            _ => Err(format!(
+                "Unknown float format '{}'. Use f16, bf16, f32, f64, or f128",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FloatClass {
+    Zero,
+    Subnormal,
+    Normal,
+    Infinite,
+    QuietNaN,
+    SignalingNaN,
+}
+
+impl std::fmt::Display for FloatClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                FloatClass::Zero => "zero",
+                FloatClass::Subnormal => "subnormal",
+                FloatClass::Normal => "normal",
+                FloatClass::Infinite => "infinite",
+                FloatClass::QuietNaN => "quiet NaN",
+                FloatClass::SignalingNaN => "signaling NaN",
+            }
+        )
+    }
+}
+
+/// A bit pattern split into its IEEE-754 fields
+#[derive(Debug, PartialEq)]
+pub struct Decoded {
+    pub negative: bool,
+    pub exponent: u64,
+    pub mantissa: BigUint,
+    pub class: FloatClass,
+    /// Exact value, for finite numbers
+    pub value: Option<BigRational>,
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   encode
+//
+// NOTES:
+//     Rounds a decimal number (or inf/nan) to the nearest value of `fmt`,
+//     ties to even, and returns its bit pattern.
+// ARGS:
+//     text - decimal float, e.g. "0.1", "-1.5e3", "inf", "nan"
+//     fmt - format to encode in
+// RETURN: The bit pattern, or an error if `text` is not a number
+//
+pub fn encode(text: &str, fmt: FloatFormat) -> Result<BigUint, Error> {
+    let text = text.trim();
+    let (negative, unsigned) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let sign = if negative {
+        BigUint::one() << (fmt.bits() - 1)
+    } else {
+        BigUint::zero()
+    };
+    let exp_field = |biased: u64| BigUint::from(biased) << fmt.man_bits;
+
+    match unsigned.to_ascii_lowercase().as_str() {
+        "inf" | "infinity" => return Ok(sign + exp_field(fmt.max_biased_exp())),
+        "nan" => {
+            let quiet = BigUint::one() << (fmt.man_bits - 1);
+            return Ok(sign + exp_field(fmt.max_biased_exp()) + quiet);
+        }
+        _ => (),
+    }
+
+    let value = match parse_decimal(unsigned) {
+        Some(v) => v,
+        None => {
+            return Err(Error::new(
+                ErrorCode::FloatErr,
+                format!("Could not read {} as a decimal float", text),
+            ));
+        }
+    };
+    if value.is_zero() {
+        return Ok(sign);
+    }
+
+    // Find the binary exponent of the leading bit, clamped to the
+    // subnormal range.
+    let mut exp2 = value.numer().bits() as i64 - value.denom().bits() as i64;
+    while pow2(exp2) > value {
+        exp2 -= 1;
+    }
+    while pow2(exp2 + 1) <= value {
+        exp2 += 1;
+    }
+    let min_exp = 1 - fmt.bias();
+    exp2 = exp2.max(min_exp);
+
+    // Scale so the significand is an integer, then round half to even
+    let scaled = value * pow2(fmt.man_bits as i64 - exp2);
+    let mut significand = scaled.floor().to_integer();
+    let remainder = scaled - BigRational::from_integer(significand.clone());
+    let half = BigRational::new(BigInt::one(), BigInt::from(2));
+    if remainder > half || (remainder == half && significand.bit(0)) {
+        significand += 1;
+    }
+    let mut significand = significand.magnitude().clone();
+
+    let implicit = BigUint::one() << fmt.man_bits;
+    if significand == &implicit << 1 {
+        significand >>= 1;
+        exp2 += 1;
+    }
+
+    if significand < implicit {
+        return Ok(sign + significand);
+    }
+
+    let biased = (exp2 + fmt.bias()) as u64;
+    if biased >= fmt.max_biased_exp() {
+        return Ok(sign + exp_field(fmt.max_biased_exp()));
+    }
+    Ok(sign + exp_field(biased) + (significand - implicit))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   decode
+//
+// NOTES:  Splits a bit pattern into its fields and works out its value
+// ARGS:
+//     pattern - bit pattern, must fit in the format
+//     fmt - format of the pattern
+// RETURN: The decoded fields, or an error if the pattern is too wide
+//
+pub fn decode(pattern: &BigUint, fmt: FloatFormat) -> Result<Decoded, Error> {
+    if pattern.bits() > u64::from(fmt.bits()) {
+        return Err(Error::new(
+            ErrorCode::FloatErr,
+            format!("Value does not fit in a {} bit {}", fmt.bits(), fmt.name),
+        ));
+    }
+
+    let mantissa = pattern & ((BigUint::one() << fmt.man_bits) - 1u32);
+    let exponent = (pattern >> fmt.man_bits).to_u64().unwrap_or(0) & fmt.max_biased_exp();
+    let negative = pattern.bit(u64::from(fmt.bits() - 1));
+
+    let (class, value) = if exponent == fmt.max_biased_exp() {
+        if mantissa.is_zero() {
+            (FloatClass::Infinite, None)
+        } else if mantissa.bit(u64::from(fmt.man_bits - 1)) {
+            (FloatClass::QuietNaN, None)
+        } else {
+            (FloatClass::SignalingNaN, None)
+        }
+    } else if exponent == 0 {
+        let value = BigRational::from_integer(BigInt::from(mantissa.clone()))
+            * pow2(1 - fmt.bias() - fmt.man_bits as i64);
+        let class = if mantissa.is_zero() {
+            FloatClass::Zero
+        } else {
+            FloatClass::Subnormal
+        };
+        (class, Some(value))
+    } else {
+        let significand = (BigUint::one() << fmt.man_bits) + &mantissa;
+        let value = BigRational::from_integer(BigInt::from(significand))
+            * pow2(exponent as i64 - fmt.bias() - fmt.man_bits as i64);
+        (FloatClass::Normal, Some(value))
+    };
+
+    let value = value.map(|v| if negative { -v } else { v });
+    Ok(Decoded {
+        negative,
+        exponent,
+        mantissa,
+        class,
+        value,
+    })
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   format_value
+//
+// NOTES:
+//     Writes the decoded value with the fewest digits that still encode
+//     back to the same bit pattern.
+// ARGS:
+//     decoded - the decoded fields
+//     fmt - format the fields came from
+// RETURN: The value as a string
+//
+pub fn format_value(decoded: &Decoded, fmt: FloatFormat) -> String {
+    let sign = if decoded.negative { "-" } else { "" };
+    let value = match &decoded.value {
+        Some(v) => v,
+        None if decoded.class == FloatClass::Infinite => return format!("{}inf", sign),
+        None => return String::from("NaN"),
+    };
+    if value.is_zero() {
+        return format!("{}0", sign);
+    }
+
+    let target = encode(&format_significant(value, fmt.max_digits()), fmt).ok();
+    (1..fmt.max_digits())
+        .map(|digits| format_significant(value, digits))
+        .find(|text| encode(text, fmt).ok() == target)
+        .unwrap_or_else(|| format_significant(value, fmt.max_digits()))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   format_fields
+//
+// NOTES:  Sign/exponent/mantissa breakdown along with the classification
+// ARGS:
+//     decoded - the decoded fields
+//     fmt - format the fields came from
+// RETURN: The breakdown as a string
+//
+pub fn format_fields(decoded: &Decoded, fmt: FloatFormat) -> String {
+    let exp_str = format!(
+        "{:0width$b}",
+        decoded.exponent,
+        width = fmt.exp_bits as usize
+    );
+    let man_str = format!(
+        "{:0>width$}",
+        decoded.mantissa.to_str_radix(2),
+        width = fmt.man_bits as usize
+    );
+    let detail = match decoded.class {
+        FloatClass::Normal => format!("normal, 2^{}", decoded.exponent as i64 - fmt.bias()),
+        FloatClass::Subnormal => format!("subnormal, 2^{}", 1 - fmt.bias()),
+        class => class.to_string(),
+    };
+    format!(
+        "sign={} exponent={} mantissa={} ({})",
+        decoded.negative as u8, exp_str, man_str, detail
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bits(text: &str, fmt: FloatFormat) -> u128 {
+        encode(text, fmt).unwrap().to_u128().unwrap()
+    }
+
+    fn round_trip(pattern: u128, fmt: FloatFormat) -> String {
+        format_value(&decode(&BigUint::from(pattern), fmt).unwrap(), fmt)
+    }
+
+    #[test]
+    fn test_encode_matches_native() {
+        for text in &["0.1", "-1.5e3", "3.4028235e38", "1e-45", "6.5e-39", "1e39"] {
+            let native: f32 = text.parse().unwrap();
+            assert_eq!(bits(text, F32), u128::from(native.to_bits()), "{}", text);
+        }
+        for text in &[
+            "0.1",
+            "2.2250738585072014e-308",
+            "5e-324",
+            "1.7976931348623157e308",
+        ] {
+            let native: f64 = text.parse().unwrap();
+            assert_eq!(bits(text, F64), u128::from(native.to_bits()), "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_encode_narrow_and_wide() {
+        assert_eq!(bits("1", F16), 0x3C00);
+        assert_eq!(bits("65504", F16), 0x7BFF);
+        assert_eq!(bits("65520", F16), 0x7C00);
+        assert_eq!(bits("-2", BF16), 0xC000);
+        assert_eq!(bits("1", F128), 0x3FFF << 112);
+        assert_eq!(bits("-inf", F32), 0xFF80_0000);
+        assert_eq!(bits("nan", F32), 0x7FC0_0000);
+        assert_eq!(
+            encode("abc", F32).map_err(|e| e.code),
+            Err(ErrorCode::FloatErr)
+        );
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(round_trip(0x3DCC_CCCD, F32), "0.1");
+        assert_eq!(round_trip(0x3555, F16), "0.3333");
+        assert_eq!(round_trip(0x0000_0001, F32), "1e-45");
+        assert_eq!(round_trip(0x8000_0000, F32), "-0");
+        assert_eq!(round_trip(0x3FB9_9999_9999_999A, F64), "0.1");
+
+        let decoded = decode(&BigUint::from(0x7F80_0001u32), F32).unwrap();
+        assert_eq!(decoded.class, FloatClass::SignalingNaN);
+        let decoded = decode(&BigUint::from(0x0000_0010u32), F32).unwrap();
+        assert_eq!(decoded.class, FloatClass::Subnormal);
+        assert_eq!(
+            format_fields(&decode(&BigUint::from(0xC000u32), F16).unwrap(), F16),
+            "sign=1 exponent=10000 mantissa=0000000000 (normal, 2^1)"
+        );
+        assert!(decode(&BigUint::from(0x1_0000u32), F16).is_err());
+    }
+}
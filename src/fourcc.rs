@@ -0,0 +1,246 @@
+////////////////////////////////////////////////////////////////////////////////
+//  Module:   fourcc.rs
+//
+//  Copyright © 2019 Zachary Nielsen
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+////////////////////////////////////////////////////////////////////////////////
+
+////////////////////////////////////////////////////////////////////////////////
+//  Namespaces
+////////////////////////////////////////////////////////////////////////////////
+use crate::endian::{self, ByteOrder};
+use num_bigint::BigUint;
+
+////////////////////////////////////////////////////////////////////////////////
+//  CODE
+////////////////////////////////////////////////////////////////////////////////
+
+/// Codes with a known meaning: V4L2 and DRM pixel formats, RIFF, AVI, and
+/// WebP chunk IDs, ISO media boxes, and codecs
+const KNOWN: &[(&str, &str)] = &[
+    // V4L2 pixel formats
+    ("YUYV", "packed YUV 4:2:2"),
+    ("UYVY", "packed YUV 4:2:2, UYVY order"),
+    ("YVYU", "packed YUV 4:2:2, YVYU order"),
+    ("VYUY", "packed YUV 4:2:2, VYUY order"),
+    ("NV12", "semi-planar YUV 4:2:0, CbCr"),
+    ("NV21", "semi-planar YUV 4:2:0, CrCb"),
+    ("NV16", "semi-planar YUV 4:2:2, CbCr"),
+    ("NV61", "semi-planar YUV 4:2:2, CrCb"),
+    ("NV24", "semi-planar YUV 4:4:4, CbCr"),
+    ("YU12", "planar YUV 4:2:0"),
+    ("YV12", "planar YUV 4:2:0, Cr before Cb"),
+    ("I420", "planar YUV 4:2:0"),
+    ("422P", "planar YUV 4:2:2"),
+    ("P010", "semi-planar YUV 4:2:0, 10 bit"),
+    ("GREY", "8 bit greyscale"),
+    ("Y10 ", "10 bit greyscale"),
+    ("Y12 ", "12 bit greyscale"),
+    ("Y16 ", "16 bit greyscale"),
+    ("RGBP", "RGB 5:6:5"),
+    ("RGB3", "RGB 8:8:8"),
+    ("BGR3", "BGR 8:8:8"),
+    ("BA81", "8 bit Bayer BGGR"),
+    ("GBRG", "8 bit Bayer GBRG"),
+    ("GRBG", "8 bit Bayer GRBG"),
+    ("RGGB", "8 bit Bayer RGGB"),
+    ("MJPG", "Motion JPEG"),
+    ("JPEG", "JPEG"),
+    ("H264", "H.264 / AVC"),
+    ("HEVC", "H.265 / HEVC"),
+    ("VP80", "VP8"),
+    ("VP90", "VP9"),
+    ("AV1F", "AV1 frame"),
+    // DRM pixel formats
+    ("XR24", "DRM XRGB8888"),
+    ("AR24", "DRM ARGB8888"),
+    ("XB24", "DRM XBGR8888"),
+    ("AB24", "DRM ABGR8888"),
+    ("RA24", "DRM RGBA8888"),
+    ("XR30", "DRM XRGB2101010"),
+    ("AR30", "DRM ARGB2101010"),
+    ("RG16", "DRM RGB565"),
+    ("BG16", "DRM BGR565"),
+    ("RG24", "DRM RGB888"),
+    ("BG24", "DRM BGR888"),
+    ("R8  ", "DRM 8 bit red"),
+    // RIFF, AVI, WAV, and WebP
+    ("RIFF", "RIFF container"),
+    ("RIFX", "RIFF container, big endian"),
+    ("LIST", "RIFF list chunk"),
+    ("JUNK", "RIFF padding chunk"),
+    ("INFO", "RIFF metadata list"),
+    ("AVI ", "AVI video file"),
+    ("avih", "AVI main header"),
+    ("strl", "AVI stream list"),
+    ("strh", "AVI stream header"),
+    ("strf", "AVI stream format"),
+    ("movi", "AVI movie data"),
+    ("idx1", "AVI index"),
+    ("vids", "AVI video stream"),
+    ("auds", "AVI audio stream"),
+    ("WAVE", "WAV audio file"),
+    ("fmt ", "WAV format chunk"),
+    ("data", "WAV sample data"),
+    ("WEBP", "WebP image"),
+    ("VP8 ", "WebP lossy bitstream"),
+    ("VP8L", "WebP lossless bitstream"),
+    // ISO media (MP4) boxes and codecs
+    ("ftyp", "ISO media file type box"),
+    ("moov", "ISO media movie box"),
+    ("mdat", "ISO media data box"),
+    ("avc1", "H.264 / AVC"),
+    ("hvc1", "H.265 / HEVC"),
+    ("hev1", "H.265 / HEVC"),
+    ("av01", "AV1"),
+    ("vp09", "VP9"),
+    ("mp4a", "MPEG-4 audio, e.g. AAC"),
+    ("mp4v", "MPEG-4 Part 2 video"),
+    ("Opus", "Opus audio"),
+    ("fLaC", "FLAC audio"),
+    ("XVID", "Xvid MPEG-4 Part 2"),
+    ("DIVX", "DivX MPEG-4 Part 2"),
+    ("DX50", "DivX 5 MPEG-4 Part 2"),
+    ("FMP4", "FFmpeg MPEG-4 Part 2"),
+];
+
+/// Name of a code `size` bytes long, e.g. "FourCC"
+pub fn name(size: usize) -> String {
+    match size {
+        2 => String::from("TwoCC"),
+        4 => String::from("FourCC"),
+        8 => String::from("EightCC"),
+        _ => format!("{} byte code", size),
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   to_code
+//
+// NOTES:
+//     The chars of a `size` byte code.  Bytes that are not printable ASCII
+//     are shown as \xNN.
+// ARGS:
+//     value - the code as a number
+//     size - bytes in the code
+//     order - byte order of the number, little puts the first char in the
+//             low byte
+// RETURN: The code, or an error if the number is too big
+//
+pub fn to_code(value: &BigUint, size: usize, order: ByteOrder) -> Result<String, String> {
+    let mut bytes = value.to_bytes_be();
+    if bytes.len() > size {
+        return Err(format!("{} does not fit in a {}", value, name(size)));
+    }
+    while bytes.len() < size {
+        bytes.insert(0, 0);
+    }
+    let bytes = endian::swap(&bytes, order, size)?;
+    Ok(bytes
+        .iter()
+        .map(|&b| {
+            if b == b' ' || b.is_ascii_graphic() {
+                (b as char).to_string()
+            } else {
+                format!("\\x{:02X}", b)
+            }
+        })
+        .collect())
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   from_code
+//
+// NOTES:  The number of a code.  Short codes are padded with spaces.
+// ARGS:
+//     code - the chars of the code
+//     size - bytes in the code
+//     order - byte order of the number
+// RETURN: The number, or an error if the code does not fit
+//
+pub fn from_code(code: &str, size: usize, order: ByteOrder) -> Result<BigUint, String> {
+    if !code.is_ascii() {
+        return Err(format!(
+            "{} is not ASCII, so it is not a {}",
+            code,
+            name(size)
+        ));
+    }
+    if code.len() > size {
+        return Err(format!(
+            "{} is longer than a {} ({} chars)",
+            code,
+            name(size),
+            size
+        ));
+    }
+    let bytes = format!("{:width$}", code, width = size).into_bytes();
+    Ok(BigUint::from_bytes_be(&endian::swap(&bytes, order, size)?))
+}
+
+/// What a known code is, e.g. "packed YUV 4:2:2" for YUYV
+pub fn describe(code: &str) -> Option<&'static str> {
+    KNOWN
+        .iter()
+        .find(|(known, _)| *known == code)
+        .map(|(_, description)| *description)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_code() {
+        let yuyv = BigUint::from(0x5659_5559u32);
+        assert_eq!(
+            to_code(&yuyv, 4, ByteOrder::Little),
+            Ok(String::from("YUYV"))
+        );
+        assert_eq!(to_code(&yuyv, 4, ByteOrder::Big), Ok(String::from("VYUY")));
+        assert_eq!(
+            to_code(&BigUint::from(0x41u32), 4, ByteOrder::Big),
+            Ok(String::from("\\x00\\x00\\x00A"))
+        );
+        assert_eq!(
+            to_code(&BigUint::from(0x4D5Au32), 2, ByteOrder::Big),
+            Ok(String::from("MZ"))
+        );
+        assert!(to_code(&BigUint::from(0x1_0000_0000u64), 4, ByteOrder::Little).is_err());
+    }
+
+    #[test]
+    fn test_from_code() {
+        assert_eq!(
+            from_code("YUYV", 4, ByteOrder::Little),
+            Ok(BigUint::from(0x5659_5559u32))
+        );
+        assert_eq!(
+            from_code("RIFF", 4, ByteOrder::Big),
+            Ok(BigUint::from(0x5249_4646u32))
+        );
+        assert_eq!(
+            from_code("Y16", 4, ByteOrder::Little),
+            Ok(BigUint::from(0x2036_3159u32))
+        );
+        assert_eq!(
+            from_code("ustar\0\0\0", 8, ByteOrder::Big).map(|v| v.to_bytes_be()),
+            Ok(b"ustar\0\0\0".to_vec())
+        );
+        assert!(from_code("YUYV2", 4, ByteOrder::Little).is_err());
+        assert!(from_code("ÿ", 4, ByteOrder::Little).is_err());
+    }
+
+    #[test]
+    fn test_describe() {
+        assert_eq!(describe("YUYV"), Some("packed YUV 4:2:2"));
+        assert_eq!(describe("fmt "), Some("WAV format chunk"));
+        assert_eq!(describe("yuyv"), None);
+        assert_eq!(name(8), "EightCC");
+    }
+}
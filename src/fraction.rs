@@ -0,0 +1,254 @@
+////////////////////////////////////////////////////////////////////////////////
+//  Module:   fraction.rs
+//
+//  Copyright © 2019 Zachary Nielsen
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+////////////////////////////////////////////////////////////////////////////////
+
+////////////////////////////////////////////////////////////////////////////////
+//  Namespaces
+////////////////////////////////////////////////////////////////////////////////
+use crate::{
+    add_separators, alphabet::Alphabet, as_string_base_with, convert_to_base_10_with, digit_error,
+    Error,
+};
+use num_bigint::{BigInt, BigUint};
+use num_integer::Integer;
+use num_rational::BigRational;
+use num_traits::{Signed, ToPrimitive, Zero};
+use std::collections::HashMap;
+
+////////////////////////////////////////////////////////////////////////////////
+//  CODE
+////////////////////////////////////////////////////////////////////////////////
+
+/// Digits of a number with a fractional part, in some base
+#[derive(Debug, PartialEq)]
+pub struct FractionDigits {
+    pub negative: bool,
+    pub int_part: String,
+    /// Fraction digits before any repeating part
+    pub frac_part: String,
+    /// Digits that repeat forever after `frac_part`
+    pub repetend: Option<String>,
+    /// Set when the digit limit was hit before the fraction ended
+    pub truncated: bool,
+}
+
+impl FractionDigits {
+    ////////////////////////////////////////////////////////////////////////////
+    // NAME:   to_separated_string
+    //
+    // NOTES:
+    //     Groups the integer part from the radix point leftward, and the
+    //     fraction from the radix point rightward.  Repeating digits are put
+    //     in parentheses, e.g. 0.0(0011), and are not grouped.
+    // ARGS:
+    //     sep_length - digits per group, 0 for no separators
+    //     sep_char - separator to put between groups
+    // RETURN: The number as a string
+    //
+    pub fn to_separated_string(&self, sep_length: u32, sep_char: char) -> String {
+        let mut out = String::new();
+        if self.negative {
+            out.push('-');
+        }
+        out.push_str(&add_separators(&self.int_part, sep_length, sep_char));
+
+        let frac_len = self.frac_part.len() + self.repetend.as_ref().map_or(0, |r| r.len());
+        if frac_len == 0 {
+            return out;
+        }
+
+        out.push('.');
+        for (idx, digit) in self.frac_part.chars().enumerate() {
+            if sep_length > 0 && idx > 0 && idx % sep_length as usize == 0 {
+                out.push(sep_char);
+            }
+            out.push(digit);
+        }
+        if let Some(repetend) = &self.repetend {
+            out.push('(');
+            out.push_str(repetend);
+            out.push(')');
+        }
+        if self.truncated {
+            out.push_str("...");
+        }
+        out
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   convert_fraction_to_base_10
+//
+// NOTES:
+//     Reads a number with a radix point, e.g. "1011.101" in base 2 or
+//     "-FF.8" in base 16, as an exact ratio.
+// ARGS:
+//     from_num - String representation of a number in `alphabet`
+//     alphabet - Digits of the base the input number is given in
+//     sep_char - Separator char to strip before converting
+// RETURN: The exact value, or an error
+//
+pub fn convert_fraction_to_base_10(
+    from_num: &str,
+    alphabet: &Alphabet,
+    sep_char: char,
+) -> Result<BigRational, Error> {
+    let num = from_num.replace(sep_char, "");
+    let (negative, unsigned) = match num.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, num.as_str()),
+    };
+    let (int_str, frac_str) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+
+    // Errors are about the whole number, not the part that failed
+    let error = || digit_error(from_num, alphabet, sep_char, true);
+    if int_str.is_empty() && frac_str.is_empty() {
+        return Err(error());
+    }
+
+    let int_part = if int_str.is_empty() {
+        BigUint::zero()
+    } else {
+        convert_to_base_10_with(int_str, alphabet, sep_char).map_err(|_| error())?
+    };
+    let frac_numer = if frac_str.is_empty() {
+        BigUint::zero()
+    } else {
+        convert_to_base_10_with(frac_str, alphabet, sep_char).map_err(|_| error())?
+    };
+    let frac_denom = BigUint::from(alphabet.base()).pow(frac_str.chars().count() as u32);
+
+    let value = BigRational::from_integer(BigInt::from(int_part))
+        + BigRational::new(BigInt::from(frac_numer), BigInt::from(frac_denom));
+    Ok(if negative { -value } else { value })
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   as_fraction_digits
+//
+// NOTES:
+//     Writes out `value` in `base` by long division.  A remainder that
+//     comes round a second time means the digits since then repeat forever.
+// ARGS:
+//     value - number to write out
+//     alphabet - digits of the output base
+//     max_digits - limit on the number of fraction digits
+// RETURN: The digits
+//
+pub fn as_fraction_digits(
+    value: &BigRational,
+    alphabet: &Alphabet,
+    max_digits: usize,
+) -> FractionDigits {
+    let magnitude = value.abs();
+    let int_part = magnitude.trunc().to_integer();
+    let int_str = as_string_base_with(int_part.magnitude(), alphabet);
+
+    let denom = magnitude.denom();
+    let mut remainder = (magnitude.numer() - &int_part * denom).magnitude().clone();
+    let denom = denom.magnitude();
+
+    let mut digits = Vec::new();
+    let mut seen: HashMap<BigUint, usize> = HashMap::new();
+    let mut repeat_start = None;
+    while !remainder.is_zero() && digits.len() < max_digits {
+        if let Some(&idx) = seen.get(&remainder) {
+            repeat_start = Some(idx);
+            break;
+        }
+        seen.insert(remainder.clone(), digits.len());
+
+        let (digit, rest) = (remainder * alphabet.base()).div_rem(denom);
+        digits.push(alphabet.digit_char(digit.to_u8().unwrap_or(0)));
+        remainder = rest;
+    }
+
+    let truncated = repeat_start.is_none() && !remainder.is_zero();
+    let (frac_part, repetend) = match repeat_start {
+        Some(idx) => (
+            digits[..idx].iter().collect(),
+            Some(digits[idx..].iter().collect()),
+        ),
+        None => (digits.iter().collect(), None),
+    };
+
+    FractionDigits {
+        negative: value.is_negative(),
+        int_part: int_str,
+        frac_part,
+        repetend,
+        truncated,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn std(base: u32) -> Alphabet {
+        Alphabet::standard(base).unwrap()
+    }
+
+    fn show(from_num: &str, from_base: u32, base: u32) -> String {
+        let value = convert_fraction_to_base_10(from_num, &std(from_base), '_').unwrap();
+        as_fraction_digits(&value, &std(base), 32).to_separated_string(0, ' ')
+    }
+
+    #[test]
+    fn test_terminating() {
+        assert_eq!(show("1011.101", 2, 10), "11.625");
+        assert_eq!(show("FF.8", 16, 10), "255.5");
+        assert_eq!(show("-.25", 10, 2), "-0.01");
+        assert_eq!(show("12.", 10, 16), "C");
+    }
+
+    #[test]
+    fn test_repeating() {
+        assert_eq!(show("0.1", 10, 2), "0.0(0011)");
+        assert_eq!(show("0.1", 3, 10), "0.(3)");
+        assert_eq!(show("1.1", 10, 16), "1.1(9)");
+
+        let value = convert_fraction_to_base_10("0.1", &std(10), '_').unwrap();
+        let digits = as_fraction_digits(&value, &std(2), 3);
+        assert!(digits.truncated);
+        assert_eq!(digits.to_separated_string(0, ' '), "0.000...");
+    }
+
+    #[test]
+    fn test_separators() {
+        let value = convert_fraction_to_base_10("12345.0625", &std(10), '_').unwrap();
+        let digits = as_fraction_digits(&value, &std(2), 32);
+        assert_eq!(digits.to_separated_string(4, '_'), "11_0000_0011_1001.0001");
+
+        let value = convert_fraction_to_base_10("0.1", &std(10), '_').unwrap();
+        let digits = as_fraction_digits(&value, &std(2), 32);
+        assert_eq!(digits.to_separated_string(4, ' '), "0.0(0011)");
+        let value = convert_fraction_to_base_10("0.03125", &std(10), '_').unwrap();
+        let digits = as_fraction_digits(&value, &std(2), 32);
+        assert_eq!(digits.to_separated_string(4, ' '), "0.0000 1");
+        let value = convert_fraction_to_base_10("0.016", &std(12), '_').unwrap();
+        let digits = as_fraction_digits(&value, &std(2), 32);
+        assert_eq!(digits.to_separated_string(4, ' '), "0.0000 0(01)");
+        assert!(convert_fraction_to_base_10(".", &std(10), '_').is_err());
+        assert!(convert_fraction_to_base_10("1.2", &std(2), '_').is_err());
+    }
+
+    #[test]
+    fn test_alphabets() {
+        let base62 = Alphabet::named("base62").unwrap();
+        let value = convert_fraction_to_base_10("z.V", &base62, '_').unwrap();
+        assert_eq!(
+            as_fraction_digits(&value, &std(10), 32).to_separated_string(0, ' '),
+            "61.5"
+        );
+        assert_eq!(show("0.5", 10, 64), "0.W");
+    }
+}
@@ -0,0 +1,362 @@
+////////////////////////////////////////////////////////////////////////////////
+//  Module:   layout.rs
+//
+//  Copyright © 2019 Zachary Nielsen
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+////////////////////////////////////////////////////////////////////////////////
+
+////////////////////////////////////////////////////////////////////////////////
+//  Namespaces
+////////////////////////////////////////////////////////////////////////////////
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+////////////////////////////////////////////////////////////////////////////////
+//  CODE
+////////////////////////////////////////////////////////////////////////////////
+
+/// A layout file as written, e.g. in TOML:
+///
+/// ```toml
+/// name = "CTRL"
+/// width = 16
+/// fields = "EN[0] MODE[3:1] DIV[15:8]"
+///
+/// [values.MODE]
+/// off = 0
+/// slow = 1
+/// fast = 2
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LayoutFile {
+    name: Option<String>,
+    width: Option<u32>,
+    fields: String,
+    #[serde(default)]
+    values: BTreeMap<String, BTreeMap<String, u64>>,
+}
+
+/// A named range of bits
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub name: String,
+    /// Most significant bit of the field
+    pub hi: u32,
+    /// Least significant bit of the field
+    pub lo: u32,
+    /// Names for some of the field's values
+    pub values: BTreeMap<String, u64>,
+}
+
+impl Field {
+    pub fn bits(&self) -> u32 {
+        self.hi - self.lo + 1
+    }
+
+    /// The bit range as written in a layout, e.g. MODE[3:1]
+    pub fn label(&self) -> String {
+        if self.hi == self.lo {
+            format!("{}[{}]", self.name, self.lo)
+        } else {
+            format!("{}[{}:{}]", self.name, self.hi, self.lo)
+        }
+    }
+
+    fn mask(&self) -> BigUint {
+        ((BigUint::one() << self.bits()) - 1u32) << self.lo
+    }
+}
+
+/// The fields of a register
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layout {
+    pub name: String,
+    /// Register width, if the layout gives one
+    pub width: Option<u32>,
+    pub fields: Vec<Field>,
+}
+
+/// One field of a decoded value
+#[derive(Debug, PartialEq)]
+pub struct FieldValue {
+    pub label: String,
+    /// The field's bits, zero padded to the field width
+    pub binary: String,
+    pub value: BigUint,
+    /// Name of the value, if the field has named values
+    pub name: Option<String>,
+}
+
+impl Layout {
+    ////////////////////////////////////////////////////////////////////////////
+    // NAME:   load
+    //
+    // NOTES:  Reads a layout file, as JSON if it ends in .json, else TOML
+    // ARGS:
+    //     path - the layout file
+    // RETURN: The layout, or a description of what is wrong with the file
+    //
+    pub fn load(path: &str) -> Result<Layout, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Could not read layout {}: {}", path, e))?;
+        let file: LayoutFile = if path.to_ascii_lowercase().ends_with(".json") {
+            serde_json::from_str(&text).map_err(|e| format!("{}: {}", path, e))?
+        } else {
+            toml::from_str(&text).map_err(|e| format!("{}: {}", path, e))?
+        };
+        Layout::from_file(file)
+    }
+
+    fn from_file(file: LayoutFile) -> Result<Layout, String> {
+        let mut layout = Layout::parse(&file.fields, file.width)?;
+        if let Some(name) = file.name {
+            layout.name = name;
+        }
+        for (field_name, values) in file.values {
+            let field = layout
+                .fields
+                .iter_mut()
+                .find(|f| f.name == field_name)
+                .ok_or_else(|| format!("Values given for unknown field {}", field_name))?;
+            if let Some((name, value)) = values
+                .iter()
+                .find(|(_, &v)| v.checked_shr(field.bits()).unwrap_or(0) != 0)
+            {
+                return Err(format!(
+                    "{} = {} does not fit in {}",
+                    name,
+                    value,
+                    field.label()
+                ));
+            }
+            field.values = values;
+        }
+        Ok(layout)
+    }
+
+    ////////////////////////////////////////////////////////////////////////////
+    // NAME:   parse
+    //
+    // NOTES:
+    //     Reads fields written as NAME[hi:lo] or NAME[bit], separated by
+    //     spaces or commas.  Fields may not overlap.
+    // ARGS:
+    //     spec - the fields, e.g. "EN[0] MODE[3:1] DIV[15:8]"
+    //     width - register width the fields must fit in, if known
+    // RETURN: The layout, or a description of the bad field
+    //
+    pub fn parse(spec: &str, width: Option<u32>) -> Result<Layout, String> {
+        let mut fields: Vec<Field> = Vec::new();
+        for item in spec.split(|c: char| c.is_whitespace() || c == ',') {
+            if item.is_empty() {
+                continue;
+            }
+            let field = parse_field(item)?;
+            if let Some(width) = width.filter(|&w| field.hi >= w) {
+                return Err(format!("{} does not fit in {} bits", field.label(), width));
+            }
+            if let Some(other) = fields.iter().find(|f| f.lo <= field.hi && field.lo <= f.hi) {
+                return Err(format!("{} overlaps {}", field.label(), other.label()));
+            }
+            fields.push(field);
+        }
+        if fields.is_empty() {
+            return Err(String::from("Layout has no fields"));
+        }
+
+        // Most significant field first, the way the register reads
+        fields.sort_by_key(|f| std::cmp::Reverse(f.hi));
+        Ok(Layout {
+            name: String::from("Register"),
+            width,
+            fields,
+        })
+    }
+
+    /// Splits `value` into its fields
+    pub fn decode(&self, value: &BigUint) -> Vec<FieldValue> {
+        self.fields
+            .iter()
+            .map(|field| {
+                let bits = (value & field.mask()) >> field.lo;
+                let binary = format!("{:0>1$}", bits.to_str_radix(2), field.bits() as usize);
+                let name = field
+                    .values
+                    .iter()
+                    .find(|(_, &v)| BigUint::from(v) == bits)
+                    .map(|(name, _)| name.clone());
+                FieldValue {
+                    label: field.label(),
+                    binary,
+                    value: bits,
+                    name,
+                }
+            })
+            .collect()
+    }
+
+    ////////////////////////////////////////////////////////////////////////////
+    // NAME:   compose
+    //
+    // NOTES:
+    //     Sets fields of `value` from NAME=VAL assignments.  VAL is one of
+    //     the field's value names, or a number (0x, 0o, and 0b prefixes are
+    //     allowed).  Bits outside the assigned fields are left alone.
+    // ARGS:
+    //     value - the value to start from
+    //     assignments - the fields to set
+    // RETURN: The new value, or a description of the bad assignment
+    //
+    pub fn compose(&self, value: &BigUint, assignments: &[String]) -> Result<BigUint, String> {
+        let mut value = value.clone();
+        for assignment in assignments {
+            let (name, text) = assignment
+                .split_once('=')
+                .ok_or_else(|| format!("Expected NAME=VALUE, got {}", assignment))?;
+            let field = self
+                .fields
+                .iter()
+                .find(|f| f.name.eq_ignore_ascii_case(name.trim()))
+                .ok_or_else(|| format!("{} has no field {}", self.name, name))?;
+
+            let text = text.trim();
+            let bits = match field.values.get(text) {
+                Some(&v) => BigUint::from(v),
+                None => parse_number(text).ok_or_else(|| {
+                    format!("{} is not a number or a value name of {}", text, field.name)
+                })?,
+            };
+            if (&bits >> field.bits()) != BigUint::zero() {
+                return Err(format!("{} does not fit in {}", text, field.label()));
+            }
+
+            let cleared = &value ^ (&value & field.mask());
+            value = cleared | (bits << field.lo);
+        }
+        Ok(value)
+    }
+}
+
+/// Reads one NAME[hi:lo] or NAME[bit] field
+fn parse_field(item: &str) -> Result<Field, String> {
+    let err = || {
+        format!(
+            "Could not read field '{}'. Use NAME[hi:lo] or NAME[bit]",
+            item
+        )
+    };
+    let (name, range) = item
+        .strip_suffix(']')
+        .and_then(|rest| rest.split_once('['))
+        .ok_or_else(err)?;
+    if name.is_empty() {
+        return Err(err());
+    }
+
+    let (hi, lo) = match range.split_once(':') {
+        Some((a, b)) => (
+            a.trim().parse::<u32>().map_err(|_| err())?,
+            b.trim().parse::<u32>().map_err(|_| err())?,
+        ),
+        None => {
+            let bit = range.trim().parse::<u32>().map_err(|_| err())?;
+            (bit, bit)
+        }
+    };
+    Ok(Field {
+        name: name.to_string(),
+        hi: hi.max(lo),
+        lo: hi.min(lo),
+        values: BTreeMap::new(),
+    })
+}
+
+/// Reads a decimal number, or one with a 0x, 0o, or 0b prefix
+fn parse_number(text: &str) -> Option<BigUint> {
+    let lower = text.to_ascii_lowercase().replace('_', "");
+    let (digits, radix) = if let Some(rest) = lower.strip_prefix("0x") {
+        (rest, 16)
+    } else if let Some(rest) = lower.strip_prefix("0o") {
+        (rest, 8)
+    } else if let Some(rest) = lower.strip_prefix("0b") {
+        (rest, 2)
+    } else {
+        (lower.as_str(), 10)
+    };
+    BigUint::parse_bytes(digits.as_bytes(), radix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctrl() -> Layout {
+        let file: LayoutFile = toml::from_str(
+            r#"
+            name = "CTRL"
+            width = 16
+            fields = "EN[0] MODE[3:1] DIV[15:8]"
+
+            [values.MODE]
+            off = 0
+            slow = 1
+            fast = 2
+            "#,
+        )
+        .unwrap();
+        Layout::from_file(file).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        let layout = ctrl();
+        assert_eq!(layout.name, "CTRL");
+        let labels: Vec<String> = layout.fields.iter().map(|f| f.label()).collect();
+        assert_eq!(labels, vec!["DIV[15:8]", "MODE[3:1]", "EN[0]"]);
+
+        assert!(Layout::parse("A[3:0] B[4:3]", None).is_err());
+        assert!(Layout::parse("A[16]", Some(16)).is_err());
+        assert!(Layout::parse("A[x]", None).is_err());
+        assert!(Layout::parse("", None).is_err());
+
+        let json: LayoutFile =
+            serde_json::from_str(r#"{"fields": "A[7:4], B[3:0]", "values": {"C": {"x": 1}}}"#)
+                .unwrap();
+        assert!(Layout::from_file(json).is_err());
+    }
+
+    #[test]
+    fn test_decode() {
+        let fields = ctrl().decode(&BigUint::from(0x1205u32));
+        assert_eq!(fields[0].binary, "00010010");
+        assert_eq!(fields[0].value, BigUint::from(0x12u32));
+        assert_eq!(fields[1].name, Some(String::from("fast")));
+        assert_eq!(fields[2].value, BigUint::from(1u32));
+    }
+
+    #[test]
+    fn test_compose() {
+        let layout = ctrl();
+        let set = |start: u32, args: &[&str]| {
+            let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+            layout.compose(&BigUint::from(start), &args)
+        };
+        assert_eq!(
+            set(0, &["EN=1", "MODE=slow", "DIV=0x12"]),
+            Ok(BigUint::from(0x1203u32))
+        );
+        assert_eq!(set(0xFFFF, &["mode=off"]), Ok(BigUint::from(0xFFF1u32)));
+        assert!(set(0, &["MODE=8"]).is_err());
+        assert!(set(0, &["MODE=medium"]).is_err());
+        assert!(set(0, &["SPEED=1"]).is_err());
+        assert!(set(0, &["EN"]).is_err());
+    }
+}
@@ -0,0 +1,485 @@
+////////////////////////////////////////////////////////////////////////////////
+//  Module:   lib.rs
+//
+//  Copyright © 2019 Zachary Nielsen
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+////////////////////////////////////////////////////////////////////////////////
+//! Number conversion between bases, alphabets, encodings, and bit level
+//! formats, as used by the `numconverter` command line tool.
+//!
+//! ```
+//! use numconverter::{Number, Separators};
+//!
+//! let num: Number = "0xBB".parse().unwrap();
+//! assert_eq!(num.to_string_base(2).unwrap(), "10111011");
+//! assert_eq!(num.format(2, &Separators::default()).unwrap(), "1011 1011");
+//! assert_eq!(Number::parse("-zz", 36).unwrap().to_string(), "-1295");
+//! ```
+#![cfg_attr(feature = "fail-on-warnings", deny(warnings))]
+
+////////////////////////////////////////////////////////////////////////////////
+//  Included Modules
+////////////////////////////////////////////////////////////////////////////////
+pub mod adc;
+pub mod alphabet;
+pub mod block;
+pub mod code;
+pub mod convert;
+pub mod decimal;
+pub mod encoding;
+pub mod endian;
+mod error;
+pub mod expr;
+pub mod fixed;
+pub mod float;
+pub mod fourcc;
+pub mod fraction;
+pub mod layout;
+pub mod literal;
+mod number;
+pub mod output;
+pub mod prefix;
+pub mod signed;
+pub mod unicode;
+pub mod waveform;
+
+////////////////////////////////////////////////////////////////////////////////
+//  Namespaces
+////////////////////////////////////////////////////////////////////////////////
+use alphabet::Alphabet;
+use endian::ByteOrder;
+use num_bigint::BigUint;
+use std::{collections::HashMap, convert::TryFrom};
+
+pub use error::{base_label, digit_error, Error, ErrorCode};
+pub use number::{Number, Separators};
+
+////////////////////////////////////////////////////////////////////////////////
+//  CODE
+////////////////////////////////////////////////////////////////////////////////
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   add_separators
+//
+// NOTES:  Puts `sep_char` between every `sep_length` digits, from the right
+// ARGS:
+//     num_str - the digits to separate
+//     sep_length - digits per group, 0 for no separators
+//     sep_char - separator to put between groups
+// RETURN: The separated digits
+//
+pub fn add_separators(num_str: &str, sep_length: u32, sep_char: char) -> String {
+    if sep_length == 0 {
+        return num_str.to_string();
+    }
+
+    // Count chars, not bytes, so custom digits may be any character
+    let count = num_str.chars().count();
+    let mut this_num_str = String::with_capacity(num_str.len() + count / sep_length as usize);
+    for (idx, c) in num_str.chars().enumerate() {
+        if idx > 0 && (count - idx).is_multiple_of(sep_length as usize) {
+            this_num_str.push(sep_char);
+        }
+        this_num_str.push(c);
+    }
+    this_num_str
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   parse_map
+//
+// NOTES:  Reads a map like "2:4,10:3" into `table`, base to length
+// ARGS:
+//     map_to_parse - the map, empty for none
+//     table - lengths per base, updated in place
+// RETURN: Nothing, or an error if the map can not be read
+//
+pub fn parse_map(map_to_parse: &str, table: &mut HashMap<String, u32>) -> Result<(), Error> {
+    if !map_to_parse.is_empty() {
+        let mut position = 0;
+        for pair in map_to_parse.split(',') {
+            let entry = pair.split_once(':')
+                .and_then(|(base, space)| Some((base, space.parse::<u32>().ok()?)));
+            match entry {
+                Some((base, space)) if !base.is_empty() => {
+                    table.insert(base.to_owned(), space);
+                }
+                _ => {
+                    return Err(Error::new(ErrorCode::SeparatorMapParseError, format!("Could not read map entry '{}'", pair))
+                        .with_input(map_to_parse)
+                        .with_position(position)
+                        .with_suggestion("separate entries with ',' and each base from its length with ':', e.g. 10:3,16:4"));
+                }
+            }
+            position += pair.chars().count() + 1;
+        }
+    }
+    Ok(())
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   parse_hex_string
+//
+// NOTES:
+//     Splits a string of digits into numbers `group` digits long, e.g. 2
+//     for one per byte.  Digits left over at the end are dropped.
+// ARGS:
+//     from_num - the digits, without separators
+//     from_base - base of the digits
+//     group - digits per number
+// RETURN: One number per group of digits, or an error
+//
+pub fn parse_hex_string(from_num: &str, from_base: u32, group: usize) -> Result<Vec<BigUint>, Error> {
+    let mut num_vec = Vec::new();
+    let mut holder = String::new();
+    for num in from_num.chars() {
+        holder.push(num);
+        if holder.len() == group {
+            num_vec.push(convert_to_base_10(&holder, from_base, ' ')?);
+            holder.clear();
+        }
+    }
+    Ok(num_vec)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   as_bytes
+//
+// NOTES:
+//     Gets the byte string to encode.  String input is already one number
+//     per byte, anything else is the big endian bytes of the number, at
+//     least `width` bits long.
+// ARGS:
+//     num_vec - the converted input
+//     is_string - the input was a string of bytes
+//     width - bit width to pad to, if any
+// RETURN: The bytes, or an error if a string number is too big for a byte
+//
+pub fn as_bytes(num_vec: &[BigUint], is_string: bool, width: Option<u32>) -> Result<Vec<u8>, Error> {
+    if is_string {
+        return num_vec.iter()
+            .map(|num| match u8::try_from(num) {
+                Ok(v) => Ok(v),
+                Err(_) => {
+                    Err(Error::new(ErrorCode::EncodingErr, format!("{} does not fit in a byte", num)))
+                }
+            })
+            .collect();
+    }
+
+    let mut bytes = Vec::new();
+    for num in num_vec {
+        let mut num_bytes = num.to_bytes_be();
+        let min_len = width.map_or(0, |w| w.div_ceil(8) as usize);
+        while num_bytes.len() < min_len {
+            num_bytes.insert(0, 0);
+        }
+        bytes.append(&mut num_bytes);
+    }
+    Ok(bytes)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   byte_swap
+//
+// NOTES:
+//     Swaps the bytes of the input to `order`, one `width` bit value at a
+//     time.  Without a width the whole input is one value.
+// ARGS:
+//     num_vec - the converted input
+//     order - byte order to swap to
+//     per_byte - the input is one number per byte
+//     width - bits per value, if any
+// RETURN: The swapped input, or an error if it does not split into values
+//
+pub fn byte_swap(
+    num_vec: &[BigUint],
+    order: ByteOrder,
+    per_byte: bool,
+    width: Option<u32>
+) -> Result<Vec<BigUint>, Error> {
+    let mut bytes = as_bytes(num_vec, per_byte, width)?;
+    let chunk_len = match width {
+        Some(w) if w % 8 == 0 => w as usize / 8,
+        Some(w) => {
+            return Err(Error::new(ErrorCode::WidthErr, format!("A {} bit width is not a whole number of bytes", w)));
+        }
+        None => {
+            // Word swaps need whole words, so pad a lone number to one
+            if !per_byte && order != ByteOrder::Little && bytes.len() % 2 == 1 {
+                bytes.insert(0, 0);
+            }
+            bytes.len()
+        }
+    };
+
+    match endian::swap(&bytes, order, chunk_len) {
+        Ok(swapped) if per_byte => Ok(swapped.into_iter().map(BigUint::from).collect()),
+        Ok(swapped) => Ok(vec![BigUint::from_bytes_be(&swapped)]),
+        Err(e) => {
+            Err(Error::new(ErrorCode::WidthErr, format!("Could not swap bytes: {}", e)))
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   get_from_base
+//
+// NOTES:  Matches one of the valid input base chars to its number
+// ARGS:   from_base - a single character representing the input base
+// RETURN:
+//     The base of the input or None if there was no match.  None either
+//     indicates an error in the base, or the base was omitted.  Either way,
+//     the result is handled downstream.
+//
+pub fn get_from_base(from_base: &str) -> Option<u32> {
+    match from_base {
+        "b" => Some(2),
+        "o" => Some(8),
+        "d" => Some(10),
+        "h" | "x" => Some(16),
+        _ => None,
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   convert_to_base_10
+//
+// NOTES:  What it says on the tin
+// ARGS:
+//     from_num - String representation of a number in base `from_base
+//     from_base - Base the input number is given in
+//     sep_char - `from_base` String may have zero or more `sep_char` in it.
+//                 The function must strip out those chars before converting.
+// RETURN: Base in base 10, or an error.
+//
+pub fn convert_to_base_10(
+    from_num: &str,
+    from_base: u32,
+    sep_char: char,
+) -> Result<BigUint, Error> {
+    match Alphabet::standard(from_base) {
+        Ok(alphabet) => convert_to_base_10_with(from_num, &alphabet, sep_char),
+        Err(e) => {
+            Err(Error::new(ErrorCode::InputBaseErr, format!("Error with input base: {}", e)))
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   convert_to_base_10_with
+//
+// NOTES:  `convert_to_base_10`, with the digits taken from `alphabet`
+// ARGS:
+//     from_num - String representation of a number in `alphabet`
+//     alphabet - Digits of the base the input number is given in
+//     sep_char - Separator char to strip before converting
+// RETURN: Base in base 10, or an error.
+//
+pub fn convert_to_base_10_with(
+    from_num: &str,
+    alphabet: &Alphabet,
+    sep_char: char,
+) -> Result<BigUint, Error> {
+    let num = from_num.replace(sep_char, "");
+
+    if num.is_empty() {
+        return Err(Error::new(ErrorCode::InputBaseErr, "No number given to convert"));
+    }
+
+    // A leading '+' is a sign, unless it is one of the digits
+    let unsigned = match num.strip_prefix('+') {
+        Some(rest) if !alphabet.contains('+') => rest,
+        _ => &num,
+    };
+
+    // Map each char to its digit value first, so the (arbitrarily long)
+    // number can be built in one pass by `from_radix_be`.
+    let digits: Option<Vec<u8>> = unsigned.chars().map(|c| alphabet.digit_value(c)).collect();
+
+    match digits
+        .filter(|d| !d.is_empty())
+        .and_then(|d| BigUint::from_radix_be(&d, alphabet.base()))
+    {
+        Some(v) => Ok(v),
+        _ => Err(digit_error(from_num, alphabet, sep_char, false)),
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   as_string_base
+//
+// NOTES:  Converts the number `num` to a string representation of base `base`
+// ARGS:
+//     num - input number
+//     base - output base
+// RETURN: The number as a string, or an error
+//
+pub fn as_string_base(num: &BigUint, base: u32) -> Result<String, String> {
+    Ok(as_string_base_with(num, &Alphabet::standard(base)?))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   as_string_base_with
+//
+// NOTES:  `as_string_base`, with the digits taken from `alphabet`
+// ARGS:
+//     num - input number
+//     alphabet - digits of the output base
+// RETURN: The number as a string
+//
+pub fn as_string_base_with(num: &BigUint, alphabet: &Alphabet) -> String {
+    num.to_radix_be(alphabet.base())
+        .iter()
+        .map(|&digit| alphabet.digit_char(digit))
+        .collect()
+}
+
+#[rustfmt::skip]
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bin() {
+        assert_eq!(as_string_base(&4u32.into(),   2).unwrap(), "100");
+        assert_eq!(as_string_base(&12u32.into(),  2).unwrap(), "1100");
+        assert_eq!(as_string_base(&187u32.into(), 2).unwrap(), "10111011");
+        assert_eq!(as_string_base(&69u32.into(),  2).unwrap(), "1000101");
+    }
+
+    #[test]
+    fn test_oct() {
+        assert_eq!(as_string_base(&4u32.into(),   8).unwrap(), "4");
+        assert_eq!(as_string_base(&12u32.into(),  8).unwrap(), "14");
+        assert_eq!(as_string_base(&187u32.into(), 8).unwrap(), "273");
+        assert_eq!(as_string_base(&69u32.into(),  8).unwrap(), "105");
+    }
+
+    #[test]
+    fn test_hex() {
+        assert_eq!(as_string_base(&4u32.into(),   16).unwrap(), "4");
+        assert_eq!(as_string_base(&12u32.into(),  16).unwrap(), "C");
+        assert_eq!(as_string_base(&187u32.into(), 16).unwrap(), "BB");
+        assert_eq!(as_string_base(&69u32.into(),  16).unwrap(), "45");
+    }
+
+    #[test]
+    fn test_convert_to_base_10() {
+        assert_eq!(
+            convert_to_base_10("10111011", 2, '_'),
+            Ok(187u32.into())
+        );
+        assert_eq!(convert_to_base_10("273", 8,  '_'), Ok(187u32.into()));
+        assert_eq!(convert_to_base_10("187", 10, '_'), Ok(187u32.into()));
+        assert_eq!(convert_to_base_10("BB" , 16, '_'), Ok(187u32.into()));
+        assert_eq!(
+            convert_to_base_10("", 10, '_').map_err(|e| e.code),
+            Err(ErrorCode::InputBaseErr)
+        );
+        // SI prefixes are read by the command line, not here
+        assert!(convert_to_base_10("4k7", 10, '_').is_err());
+    }
+
+    #[test]
+    fn test_wide_numbers() {
+        let sha256 = "E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855";
+        let num = convert_to_base_10(sha256, 16, '_').unwrap();
+        assert_eq!(num.bits(), 256);
+        assert_eq!(as_string_base(&num, 16).unwrap(), sha256);
+
+        let max_512 = "F".repeat(128);
+        let num = convert_to_base_10(&max_512, 16, '_').unwrap();
+        assert_eq!(as_string_base(&num, 2).unwrap(), "1".repeat(512));
+        assert_eq!(
+            convert_to_base_10(&as_string_base(&num, 10).unwrap(), 10, '_'),
+            Ok(num)
+        );
+
+        assert_eq!(as_string_base(&0u32.into(), 16).unwrap(), "0");
+        assert_eq!(convert_to_base_10("+", 10, '_').map_err(|e| e.code), Err(ErrorCode::BaseConversionErr));
+    }
+
+    #[test]
+    fn test_alphabets() {
+        let num = convert_to_base_10("zz", 62, '_').unwrap();
+        assert_eq!(num, BigUint::from(3843u32));
+        assert_eq!(as_string_base(&num, 64).unwrap(), "y3");
+        assert!(as_string_base(&num, 65).is_err());
+        assert_eq!(convert_to_base_10("zz", 65, '_').map_err(|e| e.code), Err(ErrorCode::InputBaseErr));
+
+        // A bitcoin style base58 token, and a Crockford ID read with aliases
+        let base58 = Alphabet::named("base58-bitcoin").unwrap();
+        let num = convert_to_base_10_with("3yR", &base58, '_').unwrap();
+        assert_eq!(num, BigUint::from(10_000u32));
+        assert_eq!(as_string_base_with(&num, &base58), "3yR");
+        let crockford = Alphabet::named("crockford").unwrap();
+        assert_eq!(convert_to_base_10_with("1o", &crockford, '_'), Ok(BigUint::from(32u32)));
+
+        // '+' is a digit of base64, not a sign
+        let base64 = Alphabet::named("base64").unwrap();
+        assert_eq!(convert_to_base_10_with("+A", &base64, '_'), Ok(BigUint::from(62u32 * 64)));
+
+        // Groups count multibyte digits as one each
+        let greek = Alphabet::custom("αβγδ").unwrap();
+        let digits = as_string_base_with(&BigUint::from(0x1Bu32), &greek);
+        assert_eq!(add_separators(&digits, 2, ' '), "β γδ");
+        assert_eq!(add_separators("éaéaéaéa", 3, ' '), "éa éaé aéa");
+        assert_eq!(add_separators("1234", 0, ' '), "1234");
+    }
+
+    #[test]
+    fn test_parse_map() {
+        let mut table = HashMap::new();
+        assert_eq!(parse_map("16:2,10:3", &mut table), Ok(()));
+        assert_eq!(table.get("10"), Some(&3));
+
+        // A missing ':' is an error pointing at the entry, not a panic
+        let err = parse_map("10:3,16", &mut table).unwrap_err();
+        assert_eq!(err.code, ErrorCode::SeparatorMapParseError);
+        assert_eq!(err.position, Some(5));
+        assert!(parse_map("16:x", &mut table).is_err());
+        assert!(parse_map(":4", &mut table).is_err());
+    }
+
+    #[test]
+    fn test_error_position() {
+        let err = convert_to_base_10("1G", 16, '_').unwrap_err();
+        assert_eq!(err.position, Some(1));
+        let base16 = Alphabet::standard(16).unwrap();
+        let err = signed::convert_signed_to_base_10("-1G", &base16, '_').unwrap_err();
+        assert_eq!((err.input.as_deref(), err.position), (Some("-1G"), Some(2)));
+        let err = fraction::convert_fraction_to_base_10("1.2Z", &base16, '_').unwrap_err();
+        assert_eq!(err.position, Some(3));
+    }
+
+    #[test]
+    fn test_as_bytes() {
+        let nums: Vec<BigUint> = vec![0x48u32.into(), 0x69u32.into()];
+        assert_eq!(as_bytes(&nums, true, None), Ok(vec![0x48, 0x69]));
+        assert_eq!(as_bytes(&[0x1234u32.into()], false, None), Ok(vec![0x12, 0x34]));
+        assert_eq!(as_bytes(&[0x1234u32.into()], false, Some(32)), Ok(vec![0, 0, 0x12, 0x34]));
+        assert_eq!(as_bytes(&[0x100u32.into()], true, None).map_err(|e| e.code), Err(ErrorCode::EncodingErr));
+    }
+
+    #[test]
+    fn test_byte_swap() {
+        let num = [BigUint::from(0x0A0B_0C0Du32)];
+        assert_eq!(byte_swap(&num, ByteOrder::Little, false, None), Ok(vec![0x0D0C_0B0Au32.into()]));
+        assert_eq!(byte_swap(&num, ByteOrder::WordSwap, false, Some(32)), Ok(vec![0x0C0D_0A0Bu32.into()]));
+        assert_eq!(byte_swap(&num, ByteOrder::Little, false, Some(12)).map_err(|e| e.code), Err(ErrorCode::WidthErr));
+
+        // Only the low word of a 24 bit number is swapped
+        let num: BigUint = 0x01_0203u32.into();
+        assert_eq!(byte_swap(&[num], ByteOrder::ByteSwap, false, None), Ok(vec![0x0100_0302u32.into()]));
+
+        let bytes: Vec<BigUint> = vec![1u32.into(), 2u32.into(), 3u32.into(), 4u32.into()];
+        let swapped = byte_swap(&bytes, ByteOrder::Little, true, Some(16)).unwrap();
+        assert_eq!(swapped, vec![2u32.into(), 1u32.into(), 4u32.into(), 3u32.into()]);
+    }
+}
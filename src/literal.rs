@@ -0,0 +1,204 @@
+////////////////////////////////////////////////////////////////////////////////
+//  Module:   literal.rs
+//
+//  Copyright © 2019 Zachary Nielsen
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+////////////////////////////////////////////////////////////////////////////////
+
+////////////////////////////////////////////////////////////////////////////////
+//  CODE
+////////////////////////////////////////////////////////////////////////////////
+
+/// A number literal as written in source code
+#[derive(Debug, PartialEq)]
+pub struct Literal {
+    pub base: u32,
+    /// The digits, with any '-' sign kept in front
+    pub digits: String,
+    /// Width declared by the literal, e.g. 16 for Verilog 16'hFF
+    pub width: Option<u32>,
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   detect
+//
+// NOTES:
+//     Recognizes literals copied from source code:
+//         C/Rust   0xFF 0b1010 0o17, with Rust type suffixes like 0xFFu8
+//         Intel    0FFh 1010b 17o
+//         Verilog  8'hFF 16'b1010_0000 'd42
+//         VHDL     x"FF" b"1010" o"17" 16#FF#
+//         Lisp     #xFF #b1010 #o17
+//     Underscores between digits are dropped.
+// ARGS:
+//     text - the literal, surrounding whitespace allowed
+// RETURN: The base, digits, and declared width, or None for plain numbers
+//
+pub fn detect(text: &str) -> Option<Literal> {
+    let text = text.trim();
+    let (sign, unsigned) = match text.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", text),
+    };
+
+    let (base, digits, width) = c_style(unsigned)
+        .or_else(|| verilog(unsigned))
+        .or_else(|| vhdl(unsigned))
+        .or_else(|| lisp(unsigned))
+        .or_else(|| intel(unsigned))?;
+
+    let digits = digits.replace('_', "");
+    let valid = !digits.is_empty() && digits.chars().all(|c| c.is_digit(base));
+    if !valid {
+        return None;
+    }
+    Some(Literal {
+        base,
+        digits: format!("{}{}", sign, digits),
+        width,
+    })
+}
+
+/// Base for a prefix letter: b, o, d, or h/x
+fn base_letter(letter: char) -> Option<u32> {
+    match letter.to_ascii_lowercase() {
+        'b' => Some(2),
+        'o' => Some(8),
+        'd' => Some(10),
+        'h' | 'x' => Some(16),
+        _ => None,
+    }
+}
+
+/// 0x/0o/0b prefixes, and Rust integer type suffixes
+fn c_style(text: &str) -> Option<(u32, &str, Option<u32>)> {
+    let (base, rest) = match text.get(..2)?.to_ascii_lowercase().as_str() {
+        "0x" => (16, &text[2..]),
+        "0o" => (8, &text[2..]),
+        "0b" => (2, &text[2..]),
+        _ => (10, text),
+    };
+
+    // A suffix like u8 or i32 gives the width.  'u' and 'i' are not digits
+    // in any of these bases, so there is no ambiguity.
+    let (digits, width) = match rest.find(['u', 'i']) {
+        Some(idx) => {
+            let bits = rest[idx + 1..].parse::<u32>().ok()?;
+            if ![8, 16, 32, 64, 128].contains(&bits) {
+                return None;
+            }
+            (rest[..idx].trim_end_matches('_'), Some(bits))
+        }
+        None if base != 10 => (rest, None),
+        // Plain decimal, not a literal that needs detecting
+        None => return None,
+    };
+    Some((base, digits, width))
+}
+
+/// Verilog [size]'[s]<base><digits>
+fn verilog(text: &str) -> Option<(u32, &str, Option<u32>)> {
+    let (size, rest) = text.split_once('\'')?;
+    let width = if size.is_empty() {
+        None
+    } else {
+        Some(size.replace('_', "").parse::<u32>().ok()?)
+    };
+    let rest = rest
+        .strip_prefix('s')
+        .or_else(|| rest.strip_prefix('S'))
+        .unwrap_or(rest);
+    let base = base_letter(rest.chars().next()?)?;
+    Some((base, &rest[1..], width))
+}
+
+/// VHDL bit strings x"FF", b"1010", o"17", which are as wide as their
+/// digits, and based literals like 16#FF#
+fn vhdl(text: &str) -> Option<(u32, &str, Option<u32>)> {
+    if let Some(rest) = text.strip_suffix('"') {
+        let (prefix, digits) = rest.split_once('"')?;
+        let mut letters = prefix.chars();
+        let base = base_letter(letters.next()?).filter(|&b| b != 10)?;
+        if letters.next().is_some() {
+            return None;
+        }
+        let width = digits.chars().filter(|&c| c != '_').count() as u32 * base.trailing_zeros();
+        return Some((base, digits, Some(width)));
+    }
+
+    let rest = text.strip_suffix('#')?;
+    let (base, digits) = rest.split_once('#')?;
+    let base = base.parse::<u32>().ok().filter(|b| (2..=16).contains(b))?;
+    Some((base, digits, None))
+}
+
+/// Lisp #xFF, #b1010, #o17
+fn lisp(text: &str) -> Option<(u32, &str, Option<u32>)> {
+    let rest = text.strip_prefix('#')?;
+    let base = base_letter(rest.chars().next()?)?;
+    Some((base, &rest[1..], None))
+}
+
+/// Intel assembler suffixes, FFh, 1010b, 17o/17q
+fn intel(text: &str) -> Option<(u32, &str, Option<u32>)> {
+    let last = text.chars().last()?;
+    let base = match last.to_ascii_lowercase() {
+        'h' => 16,
+        'o' | 'q' => 8,
+        // 1010b is binary, even though it could also be read as hex
+        'b' => 2,
+        _ => return None,
+    };
+    Some((base, &text[..text.len() - 1], None))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lit(base: u32, digits: &str, width: Option<u32>) -> Option<Literal> {
+        Some(Literal {
+            base,
+            digits: digits.to_string(),
+            width,
+        })
+    }
+
+    #[test]
+    fn test_c_style() {
+        assert_eq!(detect("0xDEAD"), lit(16, "DEAD", None));
+        assert_eq!(detect(" 0b1010_0000\n"), lit(2, "10100000", None));
+        assert_eq!(detect("-0o17"), lit(8, "-17", None));
+        assert_eq!(detect("0xFFu8"), lit(16, "FF", Some(8)));
+        assert_eq!(detect("255_u16"), lit(10, "255", Some(16)));
+        assert_eq!(detect("0xFFu7"), None);
+        assert_eq!(detect("1234"), None);
+    }
+
+    #[test]
+    fn test_hdl() {
+        assert_eq!(detect("8'hFF"), lit(16, "FF", Some(8)));
+        assert_eq!(detect("16'b1010_0000"), lit(2, "10100000", Some(16)));
+        assert_eq!(detect("'d42"), lit(10, "42", None));
+        assert_eq!(detect("8'sd5"), lit(10, "5", Some(8)));
+        assert_eq!(detect("x\"FF\""), lit(16, "FF", Some(8)));
+        assert_eq!(detect("b\"1010\""), lit(2, "1010", Some(4)));
+        assert_eq!(detect("16#FF#"), lit(16, "FF", None));
+        assert_eq!(detect("8'hXZ"), None);
+    }
+
+    #[test]
+    fn test_other() {
+        assert_eq!(detect("#xFF"), lit(16, "FF", None));
+        assert_eq!(detect("0FFh"), lit(16, "0FF", None));
+        assert_eq!(detect("1010b"), lit(2, "1010", None));
+        assert_eq!(detect("17q"), lit(8, "17", None));
+        assert_eq!(detect("12b"), None);
+        assert_eq!(detect("hello"), None);
+    }
+}
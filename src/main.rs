@@ -14,12 +14,38 @@
 ////////////////////////////////////////////////////////////////////////////////
 //  Included Modules
 ////////////////////////////////////////////////////////////////////////////////
+mod config;
+mod repl;
 
 ////////////////////////////////////////////////////////////////////////////////
 //  Namespaces
 ////////////////////////////////////////////////////////////////////////////////
-use std::{collections::HashMap, convert::TryInto, iter::FromIterator, string::ToString};
-use structopt::StructOpt;
+use numconverter::{
+    adc::{Adc, Coding},
+    alphabet::Alphabet,
+    block::{self, SampleFormat},
+    code::Code,
+    convert::{self, Options},
+    encoding::Encoding,
+    endian::ByteOrder,
+    expr,
+    fixed::{Overflow, QFormat, Rounding},
+    float::FloatFormat,
+    get_from_base,
+    layout::Layout,
+    literal,
+    output::{self, OutputFormat, Record, Row},
+    signed::SignedRepr,
+    unicode::Utf,
+    waveform::Preamble,
+    Error, ErrorCode, Number, Separators,
+};
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Read},
+    string::ToString,
+};
+use structopt::{clap::AppSettings, StructOpt};
 
 ////////////////////////////////////////////////////////////////////////////////
 //  CODE
@@ -28,257 +54,414 @@ use structopt::StructOpt;
 #[cfg(target_os = "linux")]
 const CLIPBOARD_WAIT_TIMER: std::time::Duration = std::time::Duration::from_secs(1);
 
-#[derive(PartialEq)]
-enum ErrorCode {
-    BaseConversionErr,
-    TargetBaseErr,
-    InputBaseErr,
-    SeparatorMapParseError,
-    ClipboardErr,
-}
+fn main() {
+    // Get args, then fill in the rest from the config file
+    let matches = Opt::clap().get_matches();
+    let mut opt = Opt::from_clap(&matches);
+    let result = config::apply(&mut opt, &matches);
 
-impl std::fmt::Debug for ErrorCode {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match *self {
-                ErrorCode::BaseConversionErr => "Base Conversion Error",
-                ErrorCode::TargetBaseErr => "Target Base Error",
-                ErrorCode::InputBaseErr => "Input Base Error",
-                ErrorCode::ClipboardErr => "Clipboard access Error",
-                ErrorCode::SeparatorMapParseError => "Separator Map Parse Error",
-            }
-        )
+    if opt.verbosity > 0 {
+        println!("{:?}", opt);
+    }
+
+    if let Err(err) = result.and_then(|()| run(&opt)) {
+        eprintln!("Error: {}", err);
+        std::process::exit(err.exit_code());
     }
 }
 
-fn main() -> Result<(), ErrorCode> {
-    // Get args
-    let opt = Opt::from_args();
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   run
+//
+// NOTES:  Converts the input, batch or interactive, and writes the output
+// ARGS:
+//     opt - command line options
+// RETURN: The error that sets the exit code, if any
+//
+fn run(opt: &Opt) -> Result<(), Error> {
 
-    if opt.verbosity > 0 {
-        println!("{:?}", opt);
+    if opt.interactive {
+        repl::run(opt);
+        return Ok(());
     }
 
-    //
-    // Sort out the optional indexed argument
-    //
-    let mut to_bases: Vec<String> = opt.to_bases.clone();
-    let mut sep_table: HashMap<String, u32> = [
-            ( "2".to_string(), 4),
-            ("10".to_string(), 3),
-            ("16".to_string(), 4)
-        ].iter().cloned().collect();
-    let mut pad_table: HashMap<String, u32> = [
-            ( "2".to_string(), 8),
-            ("10".to_string(), 0),
-            ("16".to_string(), 2)
-        ].iter().cloned().collect();
-    let inputs = get_base_and_num(&opt, &mut to_bases)?;
-    let from_base: u32 = inputs.0;
-    let from_num = inputs.1;
-
-    if to_bases.is_empty() {
-        if opt.is_string || opt.fourcc {
-            to_bases.push("A".to_string());
+    let mut output = Output::default();
+    let result = match (&opt.batch, &opt.block) {
+        (Some(path), _) => convert_batch(opt, path, &mut output),
+        // The block path stands in for the number, so every base is a target
+        (None, Some(path)) => convert(&batch_opt(opt, path), &mut output),
+        (None, None) => convert(opt, &mut output),
+    };
+
+    if opt.format == OutputFormat::Pretty && result.is_err() {
+        return result;
+    }
+    if opt.format != OutputFormat::Pretty {
+        // Structured output is written even when the conversion failed
+        let rendered = if opt.batch.is_some() {
+            output::render_batch(&output.records, opt.format)
+        } else {
+            if let Err(err) = &result {
+                output.record().errors.push(err.message.clone());
+            }
+            output::render(output.record(), opt.format)
+        };
+        let text = match rendered {
+            Ok(v) => v,
+            Err(e) => {
+                return Err(Error::new(ErrorCode::FormatErr, format!("Could not write {:?} output: {}", opt.format, e)));
+            }
+        };
+        if !opt.silent {
+            print!("{}", text);
         }
+        output.clipboard_buffer = text;
+    }
+
+    if opt.copy {
+        handle_clipboard(output.clipboard_buffer)?;
+    }
+    result
+}
 
-        if !opt.fourcc {
-            to_bases.append(&mut vec![
-                "2".to_string(),
-                "10".to_string(),
-                "16".to_string()
-            ])
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   convert
+//
+// NOTES:
+//     Converts the input to every target with the library, then prints the
+//     rows.  With a structured --format they are only kept, to be written
+//     at the end.
+// ARGS:
+//     opt - command line options
+//     output - collects the rows, for the clipboard and structured output
+// RETURN: Nothing, or the first error
+//
+fn convert(opt: &Opt, output: &mut Output) -> Result<(), Error> {
+    output.records.push(Record::default());
+    let result = conversion_options(opt)
+        .and_then(|options| convert::convert(&options, output.record()));
+
+    let record = output.record();
+    for warning in &record.warnings {
+        eprintln!("Warning: {}", warning);
+    }
+    if opt.format == OutputFormat::Pretty {
+        let Output { records, clipboard_buffer, .. } = output;
+        for row in &records.last().expect("a record").rows {
+            print_row(opt, row, clipboard_buffer);
         }
     }
+    output.previous = result?;
+    Ok(())
+}
 
-    // Convert input number to base 10
-    let num_vec: Vec<u128> =
-        if opt.is_string {
-            let sep_list = vec![',', '.', ' ', '-', '_', opt.sep_char];
-            let has_sep = from_num.contains(&sep_list[..]);
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   conversion_options
+//
+// NOTES:
+//     The library options for the number on the command line, with the
+//     files named by --layout and --block read in.
+// ARGS:
+//     opt - command line options
+// RETURN: The options, or what is wrong with them
+//
+fn conversion_options(opt: &Opt) -> Result<Options, Error> {
+    let mut to_bases: Vec<String> = opt.to_bases.clone();
+    let (from_base, input, literal_width) = get_base_and_num(opt, &mut to_bases)?;
+    // A width given on the command line wins over one declared by the literal
+    let width = opt.width.or(literal_width);
+    if let Some(width) = width.filter(|&w| u64::from(w) > expr::MAX_BITS) {
+        return Err(Error::new(ErrorCode::WidthErr, format!("A {} bit width is wider than the {} bit limit", width, expr::MAX_BITS)));
+    }
 
-            if !has_sep && from_base == 16 && (from_num.len() % 2) == 0 {
-                // No separators, manually split by groups of 2
-                parse_hex_string(&from_num, from_base)?
+    let mut separators = Separators {
+        sep_char: opt.sep_char,
+        default_length: opt.sep_length,
+        ..Separators::default()
+    };
+    separators.parse_sep_map(&opt.sep_map)?;
+    separators.parse_pad_map(&opt.pad_map)?;
+
+    let layout = match &opt.layout {
+        Some(path) => match Layout::load(path) {
+            Ok(v) => Some(v),
+            Err(e) => {
+                return Err(Error::new(ErrorCode::LayoutErr, format!("Error with layout: {}", e)));
             }
-            else {
-                // Gather each separated number into a vector for separate conversion
-                from_num.split(&sep_list[..])
-                    .map(|s| s.to_string())
-                    .collect::<Vec<String>>().iter()
-                    .map(|num| convert_to_base_10(num, from_base, opt.sep_char).unwrap())
-                        .collect::<Vec<u128>>()
+        },
+        None => None,
+    };
+    let adc = match opt.adc {
+        Some(bits) => match Adc::new(bits, &opt.vref, opt.coding) {
+            Ok(v) => Some(v),
+            Err(e) => {
+                return Err(Error::new(ErrorCode::AdcErr, format!("Error with --adc: {}", e)));
             }
+        },
+        None => None,
+    };
+    let block = match &opt.block {
+        Some(_) => Some(read_block(&input)?),
+        None => None,
+    };
+
+    Ok(Options {
+        from_base,
+        from_alphabet: opt.from_alphabet.clone(),
+        width,
+        targets: to_bases,
+        to_alphabet: opt.to_alphabet.clone(),
+        default_to: opt.default_to.clone(),
+        separators,
+        no_sep: opt.no_sep,
+        frac_digits: opt.frac_digits,
+        prefixes: true,
+        expr: opt.expr,
+        is_string: opt.is_string,
+        text: opt.text,
+        utf: opt.utf,
+        join: opt.join,
+        from_encoding: opt.from_encoding,
+        from_code: opt.from_code,
+        fourcc: opt.fourcc,
+        fourcc_size: opt.fourcc_size,
+        fourcc_endian: opt.fourcc_endian,
+        block,
+        byt_nr: opt.byt_nr,
+        byt_or: opt.byt_or,
+        bn_fmt: opt.bn_fmt,
+        signed_repr: opt.signed_repr,
+        float: opt.float,
+        q_format: opt.q_format,
+        rounding: opt.rounding,
+        overflow: opt.overflow,
+        layout,
+        field: opt.field.clone(),
+        preamble: waveform_preamble(opt)?,
+        adc,
+        scaled: opt.scaled,
+        endian: opt.endian.clone(),
+        input,
+    })
+}
+
+/// Where the converted values go
+#[derive(Default)]
+struct Output {
+    /// Content for the clipboard
+    clipboard_buffer: String,
+    /// Rows kept for --format, one record per input
+    records: Vec<Record>,
+    /// The value converted last, if it was a single whole number
+    previous: Option<Number>,
+}
+
+impl Output {
+    /// The record of the input being converted
+    fn record(&mut self) -> &mut Record {
+        if self.records.is_empty() {
+            self.records.push(Record::default());
         }
-        else if opt.fourcc {
-            // Convert input number to base 16
-            let this_num_str = as_string_base(&convert_to_base_10(&from_num, from_base, opt.sep_char)?, 16).expect("can parse fourcc input number");
-            // Split that by hex num
-            parse_hex_string(&this_num_str, 16)?
-        }
-        else {
-            vec![convert_to_base_10(&from_num, from_base, opt.sep_char)?]
-        };
+        self.records.last_mut().unwrap()
+    }
+}
 
-    parse_map(&opt.sep_map, &mut sep_table)?;
-    parse_map(&opt.pad_map, &mut pad_table)?;
-
-    // Buffer to store content for the clipboard
-    let mut clipboard_buffer = String::default();
-
-    // Print conversions
-    for target_base in to_bases {
-        let (base_str, out_str) =
-            if target_base.to_ascii_uppercase() == "A" {
-                // Convert to ascii
-                let out_str: String =
-                    num_vec.iter().map(|c| *c as u8 as char).collect();
-                if opt.fourcc {
-                    (String::from("FOURCC"), out_str.chars().rev().collect())
-                }
-                else {
-                    (String::from("ASCII"), out_str)
-                }
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   convert_batch
+//
+// NOTES:
+//     Converts every value in a file, or stdin, with the same options.  A
+//     value that fails is reported with its line and the run goes on.
+// ARGS:
+//     opt - command line options
+//     path - the file to read, "-" for stdin
+//     output - collects the rows, one record per value
+// RETURN: Nothing, or once every value was tried, an error counting the ones
+//         that failed with the category of the first
+//
+fn convert_batch(opt: &Opt, path: &str, output: &mut Output) -> Result<(), Error> {
+    let reader: Box<dyn BufRead> = if path == "-" {
+        Box::new(BufReader::new(std::io::stdin()))
+    } else {
+        match File::open(path) {
+            Ok(v) => Box::new(BufReader::new(v)),
+            Err(e) => {
+                return Err(Error::new(ErrorCode::BatchErr, format!("Could not read {}: {}", path, e)));
             }
-            else {
-                let custom_base = match u32::from_str_radix(&target_base, 10) {
-                    Ok(v) => v,
-                    Err(_) => {
-                        println!(
-                            "Error with target base {}\nPlease provide target base is base 10.",
-                            target_base
-                        );
-                        return Err(ErrorCode::TargetBaseErr);
-                    }
-                };
-
-                let mut out_str = String::from("");
-                for num in num_vec.iter() {
-                    let mut this_num_str = match as_string_base(&num, custom_base) {
-                        Ok(v) => v,
-                        Err(e) => {
-                            println!("Error with custom base:\n\t{}", e);
-                            return Err(ErrorCode::InputBaseErr);
-                        }
-                    };
-
-                    // Get the separator length for this base
-                    let sep_length = if sep_table.contains_key(&target_base) {
-                        sep_table.get(&target_base).unwrap().clone()
-                    } else {
-                        opt.sep_length
-                    };
-
-                    //
-                    // Pad the print string with separator characters if needed
-                    //
-                    if opt.is_string {
-                        let mut pre = String::new();
-                        if pad_table.contains_key(&target_base) {
-                            let mut count = 0;
-                            while this_num_str.len() + count < pad_table[&target_base] as usize {
-                                pre.push('0');
-                                count += 1;
-                            }
-                        }
-                        this_num_str = pre + &this_num_str;
-                        this_num_str.push(opt.sep_char);
-                    }
-                    else if !opt.no_sep && sep_length > 0 {
-                        // Pad string every opt.spacer_length characters
-                        // Need size-1/spacer_len additional slots in the string
-                        let mut insert_idx: i32 = this_num_str.len() as i32 - sep_length as i32;
-                        while insert_idx > 0 {
-                            let left = String::from(&this_num_str[..(insert_idx as usize)]);
-                            let right = String::from(&this_num_str[(insert_idx as usize)..]);
-                            this_num_str = left;
-                            this_num_str.push(opt.sep_char);
-                            this_num_str.push_str(&right);
-                            insert_idx -= sep_length as i32;
-                        }
-                    }
-
-                    // Append to final out string
-                    out_str.push_str(&this_num_str);
-                }
-                if opt.is_string {
-                    out_str.pop();
-                }
-                (custom_base.to_string(), out_str)
-            };
+        }
+    };
+    let pretty = opt.format == OutputFormat::Pretty && !opt.silent;
+
+    let mut first_err = None;
+    let mut failed = 0;
+    for (idx, line) in reader.lines().enumerate() {
+        let line = match line {
+            Ok(v) => v,
+            Err(e) => {
+                return Err(Error::new(ErrorCode::BatchErr, format!("Could not read line {} of {}: {}", idx + 1, path, e)));
+            }
+        };
+        // Strings and expressions have spaces in them, so take the whole line
+        let values: Vec<&str> = if opt.is_string || opt.text || opt.expr {
+            vec![line.trim()]
+        } else {
+            line.split_whitespace().collect()
+        };
 
-        // Print results
-        if !opt.silent {
-            if !opt.bare {
-                print!("Base {:02}: ", &base_str);
+        for value in values.into_iter().filter(|v| !v.is_empty()) {
+            if pretty && !opt.bare {
+                println!("Line {}: {}", idx + 1, value);
             }
-            println!("{}", out_str);
-        }
-        if opt.copy {
-            if !opt.bare {
-                clipboard_buffer += &format!("Base {:02}: ", &base_str);
+            let result = convert(&batch_opt(opt, value), output);
+            output.record().line = Some(idx + 1);
+            if let Err(err) = result {
+                eprintln!("Line {}: {}", idx + 1, err);
+                output.record().errors.push(err.message.clone());
+                first_err.get_or_insert(err.code);
+                failed += 1;
             }
-            clipboard_buffer += &format!("{}\n", out_str);
         }
     }
-
-    if opt.copy {
-        handle_clipboard(clipboard_buffer)
-    } else {
-        Ok(())
+    // Each error was already shown with its line, so only count them
+    match first_err {
+        Some(code) => Err(Error::new(code, format!("{} value(s) in {} could not be converted", failed, path))),
+        None => Ok(()),
     }
 }
 
-fn parse_map(map_to_parse: &str, table: &mut HashMap<String, u32>) -> Result<(), ErrorCode> {
-    if map_to_parse != "" {
-        let map = map_to_parse.split(',');
-        for pair in map {
-            let vec_pair = Vec::from_iter(pair.split(':'));
-            let base = vec_pair[0];
-            let space = match u32::from_str_radix(vec_pair[1], 10) {
-                Ok(num) => num,
-                Err(_) => return map_parse_err_print(),
-            };
-
-            table.insert(base.to_owned(), space);
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   waveform_preamble
+//
+// NOTES:
+//     The waveform scaling of --preamble, with the --ymult style options
+//     set over it.  Without any of them there is no scaling.
+// ARGS:
+//     opt - command line options
+// RETURN: The preamble if one was given, or what is wrong with it
+//
+fn waveform_preamble(opt: &Opt) -> Result<Option<Preamble>, Error> {
+    let params = [
+        ("YMULT", &opt.ymult),
+        ("YOFF", &opt.yoff),
+        ("YZERO", &opt.yzero),
+        ("XINCR", &opt.xincr),
+        ("XZERO", &opt.xzero),
+        ("PT_OFF", &opt.pt_off),
+    ];
+    if opt.preamble.is_none() && params.iter().all(|(_, value)| value.is_none()) {
+        return Ok(None);
+    }
+
+    let mut preamble = match &opt.preamble {
+        Some(text) => match text.parse::<Preamble>() {
+            Ok(v) => v,
+            Err(e) => {
+                return Err(Error::new(ErrorCode::WaveformErr, format!("Could not read the preamble: {}", e)));
+            }
+        },
+        None => Preamble::default(),
+    };
+    for (keyword, value) in params.iter() {
+        if let Some(value) = value {
+            if let Err(e) = preamble.set(keyword, value) {
+                return Err(Error::new(ErrorCode::WaveformErr, e));
+            }
         }
     }
-    Ok(())
+    Ok(Some(preamble))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   read_block
+//
+// NOTES:  Reads a binary block, from a file or stdin
+// ARGS:
+//     path - the file to read, "-" for stdin
+// RETURN: The bytes of the block, or an error
+//
+fn read_block(path: &str) -> Result<Vec<u8>, Error> {
+    let mut data = Vec::new();
+    let read = if path == "-" {
+        std::io::stdin().read_to_end(&mut data)
+    } else {
+        File::open(path).and_then(|mut f| f.read_to_end(&mut data))
+    };
+    if let Err(e) = read {
+        return Err(Error::new(ErrorCode::BlockErr, format!("Could not read {}: {}", path, e)));
+    }
+    Ok(data)
 }
 
-fn map_parse_err_print() -> Result<(), ErrorCode> {
-    println!("Error parsing map.");
-    println!("Ensure separate entries are separated with ','");
-    println!("Ensure base/space numbers are separated with ':'");
-    return Err(ErrorCode::SeparatorMapParseError);
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   batch_opt
+//
+// NOTES:
+//     The options for one --batch value.  The positional arguments are all
+//     bases then, an optional base char followed by the targets.
+// ARGS:
+//     opt - command line options
+//     value - the number to convert
+// RETURN: The options, as if `value` was given on the command line
+//
+fn batch_opt(opt: &Opt, value: &str) -> Opt {
+    let mut bases: Vec<String> = opt.from_base_char.iter()
+        .chain(opt.from_num.iter())
+        .chain(opt.to_bases.iter())
+        .cloned()
+        .collect();
+
+    let mut item = opt.clone();
+    item.from_base_char = if bases.first().and_then(|b| get_from_base(b)).is_some() {
+        Some(bases.remove(0))
+    } else {
+        None
+    };
+    // The value is never taken for a base char, even one like b or d
+    item.from_num = Some(value.to_string());
+    item.to_bases = bases;
+    item
 }
 
-fn parse_hex_string(from_num: &str, from_base: u32) -> Result<Vec<u128>, ErrorCode> {
-    let mut num_vec = Vec::new();
-    let mut holder = String::new();
-    for num in from_num.chars() {
-        holder.push(num);
-        if holder.len() == 2 {
-            num_vec.push(convert_to_base_10(&holder, from_base, ' ')?);
-            holder.clear();
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   print_row
+//
+// NOTES:  Prints one converted value, and keeps it for the clipboard
+// ARGS:
+//     opt - command line options
+//     row - the converted value, and the base it is in
+//     clipboard - content for the clipboard so far
+//
+fn print_row(opt: &Opt, row: &Row, clipboard: &mut String) {
+    if !opt.silent {
+        if !opt.bare {
+            print!("Base {:02}: ", row.base);
+        }
+        println!("{}", row.value);
+    }
+    if opt.copy {
+        if !opt.bare {
+            *clipboard += &format!("Base {:02}: ", row.base);
         }
+        *clipboard += &format!("{}\n", row.value);
     }
-    Ok(num_vec)
 }
 
+fn clipboard_error() -> Error {
+    Error::new(ErrorCode::ClipboardErr, "Could not access the clipboard")
+}
 
 #[cfg(target_os = "linux")]
-fn handle_clipboard(content: String) -> Result<(), ErrorCode> {
+fn handle_clipboard(content: String) -> Result<(), Error> {
     use nix::unistd::{fork, ForkResult};
     use x11_clipboard::Clipboard;
 
-    match fork() {
-        Err(_) => Err(ErrorCode::ClipboardErr),
+    // Safety: the child only touches the clipboard and exits, it never
+    // returns into the rest of `main` with shared state.
+    match unsafe { fork() } {
+        Err(_) => Err(clipboard_error()),
         Ok(ForkResult::Child) => {
             let clipboard = Clipboard::new()
-                .map_err(|_e| ErrorCode::ClipboardErr)
+                .map_err(|_e| clipboard_error())
                 .unwrap();
             let conn = &clipboard.setter.connection;
 
@@ -291,7 +474,7 @@ fn handle_clipboard(content: String) -> Result<(), ErrorCode> {
                 .unwrap();
 
             while let Ok(()) = conn.has_error() {
-                if x11_clipboard::xcb::get_selection_owner(&conn, clipboard.setter.atoms.clipboard)
+                if x11_clipboard::xcb::get_selection_owner(conn, clipboard.setter.atoms.clipboard)
                     .get_reply()
                     .map(|reply| reply.owner() != clipboard.setter.window)
                     .unwrap_or(true)
@@ -307,20 +490,20 @@ fn handle_clipboard(content: String) -> Result<(), ErrorCode> {
 }
 
 #[cfg(not(target_os = "linux"))]
-fn handle_clipboard(content: String) -> Result<(), ErrorCode> {
+fn handle_clipboard(content: String) -> Result<(), Error> {
     use clipboard::ClipboardProvider;
 
-    let mut clipboard = clipboard::ClipboardContext::new().map_err(|_e| ErrorCode::ClipboardErr)?;
+    let mut clipboard = clipboard::ClipboardContext::new().map_err(|_e| clipboard_error())?;
     clipboard
         .set_contents(content)
-        .map_err(|_e| ErrorCode::ClipboardErr)
+        .map_err(|_e| clipboard_error())
 }
 
 #[cfg(target_os = "linux")]
-fn get_clipboard_content() -> Result<Option<String>, ErrorCode> {
+fn get_clipboard_content() -> Result<String, Error> {
     use x11_clipboard::Clipboard;
 
-    let clipboard = Clipboard::new().map_err(|_| ErrorCode::ClipboardErr)?;
+    let clipboard = Clipboard::new().map_err(|_| clipboard_error())?;
     let val = clipboard
         .load(
             clipboard.setter.atoms.clipboard,
@@ -328,43 +511,23 @@ fn get_clipboard_content() -> Result<Option<String>, ErrorCode> {
             clipboard.setter.atoms.property,
             std::time::Duration::from_secs(3),
         )
-        .map_err(|_| ErrorCode::ClipboardErr)?;
+        .map_err(|_| clipboard_error())?;
     let content = String::from_utf8(val).unwrap();
 
-    Ok(Some(content.trim().to_string()))
+    Ok(content.trim().to_string())
 }
 
 #[cfg(not(target_os = "linux"))]
-fn get_clipboard_content() -> Result<String, ErrorCode> {
+fn get_clipboard_content() -> Result<String, Error> {
     use clipboard::ClipboardProvider;
 
-    let mut clipboard = clipboard::ClipboardContext::new().map_err(|_| ErrorCode::ClipboardErr)?;
+    let mut clipboard = clipboard::ClipboardContext::new().map_err(|_| clipboard_error())?;
     let content = clipboard
         .get_contents()
-        .map_err(|_| ErrorCode::ClipboardErr)?;
+        .map_err(|_| clipboard_error())?;
     Ok(content.trim().to_string())
 }
 
-////////////////////////////////////////////////////////////////////////////////
-// NAME:   get_from_base
-//
-// NOTES:  Matches one of the valid input base chars to its number
-// ARGS:   from_base - a single character representing the input base
-// RETURN:
-//     The base of the input or None if there was no match.  None either
-//     indicates an error in the base, or the base was omitted.  Either way,
-//     the result is handled downstream.
-//
-fn get_from_base(from_base: &str) -> Option<u32> {
-    match from_base {
-        "b" => Some(2),
-        "o" => Some(8),
-        "d" => Some(10),
-        "h" | "x" => Some(16),
-        _ => None,
-    }
-}
-
 ////////////////////////////////////////////////////////////////////////////////
 // NAME:   get_base_and_num
 //
@@ -375,16 +538,51 @@ fn get_from_base(from_base: &str) -> Option<u32> {
 //     opt - command line options
 //     to_bases - the list of bases to convert to (possibly empty)
 // RETURN:
-//     A tuple - (from_base, from_num)
+//     A tuple - (from_base, from_num, width)
 //         from_base - the base we are converting from
 //         from_num  - the number to convert, given in base specified
 //                     by from_base
+//         width     - bit width declared by a literal like 8'hFF
+//
+fn get_base_and_num(opt: &Opt, to_bases: &mut Vec<String>) -> Result<(u32, String, Option<u32>), Error> {
+    let (from_base, from_num) = get_base_and_raw_num(opt, to_bases)?;
+
+    // Without any base given, the number may say which base it is in,
+    // e.g. 0xDEAD copied from source code
+    let base_given = get_from_base(opt.from_base_char.as_deref().unwrap_or("")).is_some()
+        || opt.from_base != 10
+        || opt.from_alphabet.is_some();
+    if !base_given && !opt.expr && !opt.is_string && !opt.text && opt.from_encoding.is_none() && opt.block.is_none() {
+        if let Some(lit) = literal::detect(&from_num) {
+            if opt.verbosity > 0 {
+                println!("Read {} as base {}", from_num.trim(), lit.base);
+            }
+            return Ok((lit.base, lit.digits, lit.width));
+        }
+    }
+    Ok((from_base, from_num, None))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   get_base_and_raw_num
+//
+// NOTES:
+//     `get_base_and_num`, before looking for a literal syntax.  A number
+//     without a base char before it is a --batch value.
+// ARGS:
+//     opt - command line options
+//     to_bases - the list of bases to convert to (possibly empty)
+// RETURN: A tuple - (from_base, from_num)
 //
-fn get_base_and_num(opt: &Opt, to_bases: &mut Vec<String>) -> Result<(u32, String), ErrorCode> {
+fn get_base_and_raw_num(opt: &Opt, to_bases: &mut Vec<String>) -> Result<(u32, String), Error> {
     let from_base_char = opt.from_base_char.clone().unwrap_or("".to_string());
-    match get_from_base(from_base_char.as_str()) {
-        Some(v) => Ok((v, opt.from_num.clone().expect("an input number"))),
-        None => {
+    match (get_from_base(from_base_char.as_str()), &opt.from_num) {
+        (Some(v), Some(from_num)) => Ok((v, from_num.clone())),
+        (Some(_), None) => {
+            Err(Error::new(ErrorCode::InputBaseErr, format!("No number given after the base char {}", from_base_char)))
+        }
+        (None, Some(from_num)) if opt.from_base_char.is_none() => Ok((opt.from_base, from_num.clone())),
+        (None, _) => {
             // No base_char. Push from_num to the bases Vec, push base_char to from_num.
             if let Some(a_base) = &opt.from_num {
                 to_bases.insert(0, a_base.clone());
@@ -394,7 +592,7 @@ fn get_base_and_num(opt: &Opt, to_bases: &mut Vec<String>) -> Result<(u32, Strin
                 // base_char wasn't provided, use the `-b` flag value as the base.
                 Ok((opt.from_base, from_base_char))
             } else {
-                if from_base_char != "" {
+                if !from_base_char.is_empty() {
                     to_bases.insert(0, from_base_char.clone());
                 }
                 // base_char wasn't provided, use the `-b` flag value as the base.
@@ -406,82 +604,11 @@ fn get_base_and_num(opt: &Opt, to_bases: &mut Vec<String>) -> Result<(u32, Strin
     }
 }
 
-////////////////////////////////////////////////////////////////////////////////
-// NAME:   convert_to_base_10
-//
-// NOTES:  What it says on the tin
-// ARGS:
-//     from_num - String representation of a number in base `from_base
-//     from_base - Base the input number is given in
-//     sep_char - `from_base` String may have zero or more `sep_char` in it.
-//                 The function must strip out those chars before converting.
-// RETURN: Base in base 10, or an error.
-//
-fn convert_to_base_10(
-    from_num: &str,
-    from_base: u32,
-    sep_char: char,
-) -> Result<u128, ErrorCode> {
-    let num = from_num.replace(sep_char, "");
-
-    match u128::from_str_radix(&num, from_base) {
-        Ok(v) => Ok(v),
-        Err(_e) => {
-            println!("Could not convert {} from base {}", from_num, from_base);
-            return Err(ErrorCode::BaseConversionErr);
-        }
-    }
-}
-
-////////////////////////////////////////////////////////////////////////////////
-// NAME:   as_string_base
-//
-// NOTES:  Converts the number `num` to a string representation of base `base`
-// ARGS:
-//     num - input number
-//     base - output base
-// RETURN: The number as a string, or an error
-//
-fn as_string_base(num: &u128, base: u32) -> Result<String, String> {
-    if base < 2 || base > 33 {
-        Err(String::from(
-            "Invalid Base.  Base must be between 2 and 32 inclusive",
-        ))
-    } else {
-        let mut str_num = String::new();
-
-        let mut tmp: u128 = *num;
-        let mut count: u32 = 0;
-
-        while tmp > 0 {
-            let radix_mask: u128 = u128::from((base as u128).pow(count));
-            let digit: u8 = match ((tmp / radix_mask) % u128::from(base)).try_into() {
-                Ok(v) => v,
-                Err(_) => {
-                    return Err(format!("Error while trying to convert to radix {}", base));
-                }
-            };
-
-            let ch = if digit >= 10 {
-                (b'A' + (digit - 10)) as char
-            } else {
-                (b'0' + digit) as char
-            };
-
-            str_num = ch.to_string() + str_num.as_str();
-
-            count += 1;
-            tmp -= u128::from(digit) * radix_mask;
-        }
-
-        Ok(str_num)
-    }
-}
-
-#[derive(StructOpt, Debug)]
+#[derive(StructOpt, Debug, Clone)]
 #[structopt(
     name = "numconverter",
-    about = "A CLI number conversion utility written in Rust"
+    about = "A CLI number conversion utility written in Rust",
+    setting = AppSettings::AllowNegativeNumbers
 )]
 struct Opt {
     /// Pad the output with a number of leading 0s
@@ -527,6 +654,44 @@ struct Opt {
     #[structopt(long)]
     from_clipboard: bool,
 
+    /// Convert every value in this file ("-" for stdin) with the same options
+    ///
+    /// Values are separated by spaces or newlines, one per line with --str
+    /// or --expr.  The positional arguments are then all bases: an optional
+    /// base char, then the targets.  A value that fails to convert is
+    /// reported with its line, and the rest are still converted.
+    #[structopt(long, conflicts_with = "from-clipboard")]
+    batch: Option<String>,
+
+    /// Convert the samples of an IEEE 488.2 binary block in this file ("-" for stdin)
+    ///
+    /// The block is #<n><len><bytes>, or #0<bytes> up to the final newline,
+    /// as sent for CURVE? queries.  The payload is split into --byt-nr byte
+    /// samples in --byt-or order, shown as their signed (S) or unsigned (U)
+    /// value by --bn-fmt.  The positional arguments are all targets.
+    #[structopt(long, conflicts_with_all = &["from-clipboard", "batch", "is-string", "text", "expr", "fourcc", "join"])]
+    block: Option<String>,
+
+    /// Bytes per --block sample, or per number of -s hex digits without separators
+    #[structopt(long, default_value = "1", parse(try_from_str = block::sample_width))]
+    byt_nr: usize,
+
+    /// Byte order of --block samples: msb (big) or lsb (little)
+    #[structopt(long, default_value = "msb")]
+    byt_or: ByteOrder,
+
+    /// --block samples are signed (ri) or unsigned (rp)
+    #[structopt(long, default_value = "ri")]
+    bn_fmt: SampleFormat,
+
+    /// Interactive mode: convert each line typed, keeping the settings
+    ///
+    /// The options given are the starting settings, and can be changed
+    /// with :set commands.  `_` is the previous result.  History is kept
+    /// in ~/.numconverter_history.
+    #[structopt(short, long, conflicts_with_all = &["from-clipboard", "batch", "block"])]
+    interactive: bool,
+
     /// Disable Pretty Print
     #[structopt(short, long)]
     bare: bool,
@@ -535,14 +700,228 @@ struct Opt {
     #[structopt(short = "s", long = "str")]
     is_string: bool,
 
+    /// Input is text, shown as its code units in --utf
+    #[structopt(short, long, conflicts_with = "is-string")]
+    text: bool,
+
+    /// Show --text or --str input as one number, its bytes in this order (big, little, word, or pdp)
+    ///
+    /// Big puts the first byte first, as in a magic number written out,
+    /// little reads the bytes as a little endian value in memory would.
+    #[structopt(long)]
+    join: Option<ByteOrder>,
+
+    /// Unicode encoding of strings (utf8, utf16, utf16le, utf16be, utf32, utf32le, or utf32be)
+    ///
+    /// --text input is encoded to it, and --str input is read as its code
+    /// units, or as their bytes for the LE and BE forms.  UTF-8 by default.
+    /// The A target shows the text, CP its code points, and NAME the
+    /// character names.
+    #[structopt(long)]
+    utf: Option<Utf>,
+
+    /// Output format (pretty, json, csv, toml, or yaml)
+    ///
+    /// The structured formats hold the input, its base, and each row with
+    /// and without separators, plus any error, for use from scripts.
+    #[structopt(long, default_value = "pretty")]
+    format: OutputFormat,
+
     /// Verbosity (more v's = more verbose)
     #[structopt(short, long, parse(from_occurrences))]
     verbosity: u8,
 
+    /// Input is an expression, e.g. "0x1F << 4 | 0b101" or "(1<<20)-1"
+    ///
+    /// Operators are + - * / % ** << >> & | ^ ~ and parentheses, with C
+    /// precedence.  Literals take a 0x, 0o, or 0b prefix, or are in the
+    /// input base.  With --width the result must fit in that many bits.
+    #[structopt(short, long)]
+    expr: bool,
+
     /// To FourCC - converts the input to a fourcc output
-    #[structopt(long)]
+    ///
+    /// A number is shown as its code, and a code (or --text) as its number.
+    /// Known codes, like V4L2 and DRM pixel formats, RIFF chunk IDs, and
+    /// codecs, are described: YUYV – packed YUV 4:2:2.
+    #[structopt(long, conflicts_with = "is-string")]
     fourcc: bool,
 
+    /// Bytes in a --fourcc code: 2 (TwoCC), 4 (FourCC), or 8 (EightCC)
+    #[structopt(long, default_value = "4", possible_values = &["2", "4", "8"])]
+    fourcc_size: usize,
+
+    /// Byte order of --fourcc codes (little or big)
+    ///
+    /// Little puts the first char in the low byte, as V4L2 and DRM do.
+    #[structopt(long, default_value = "little")]
+    fourcc_endian: ByteOrder,
+
+    /// Bit width for signed numbers
+    ///
+    /// Negative inputs are shown as a bit pattern of this width, and the
+    /// pattern is also shown as its signed (S) and unsigned (U) value.
+    #[structopt(short, long)]
+    width: Option<u32>,
+
+    /// Representation of negative numbers with --width (twos, ones, or sign-mag)
+    #[structopt(long, default_value = "twos")]
+    signed_repr: SignedRepr,
+
+    /// IEEE-754 float mode (f16, bf16, f32, f64, or f128)
+    ///
+    /// Decimal input is encoded to its bit pattern.  Input in any other base
+    /// is read as a bit pattern and decoded to the float (F) and its fields.
+    #[structopt(long)]
+    float: Option<FloatFormat>,
+
+    /// Fixed point mode (Qm.n or UQm.n, sign bit counted in m)
+    ///
+    /// Decimal input is a real number that gets quantized to the raw
+    /// integer.  Input in any other base is the raw integer, decoded to its
+    /// real value (Q).
+    #[structopt(long = "q")]
+    q_format: Option<QFormat>,
+
+    /// Alphabet of the input number (a name like base62, base58, crockford, or the digits)
+    ///
+    /// The input base is the number of digits in the alphabet.
+    #[structopt(long)]
+    from_alphabet: Option<Alphabet>,
+
+    /// Also output in this alphabet (a name or the digits) [repeatable]
+    ///
+    /// Alphabet names can also be given as bases to convert to.
+    #[structopt(long, number_of_values = 1)]
+    to_alphabet: Vec<Alphabet>,
+
+    /// Input is a byte string in this encoding (b64, b64url, b32, b58, a85, or z85)
+    ///
+    /// The bytes are shown like --str input.  The same names can be given
+    /// as bases to convert to, to encode the bytes of any input.
+    #[structopt(long)]
+    from_encoding: Option<Encoding>,
+
+    /// Input is a bit pattern in this code (gray, bcd, ubcd, xs3, or excess-N)
+    ///
+    /// Give the pattern in base 2 or 16, e.g. --from-code bcd h 4700.  The
+    /// same names can be given as bases to convert to, to show any whole
+    /// number in the code.  xs3 is the decimal excess-3 code, one digit per
+    /// nibble, other excess-N codes are the value plus N.
+    #[structopt(long, conflicts_with_all = &["is-string", "text", "expr", "fourcc", "block", "float", "q-format", "scaled"])]
+    from_code: Option<Code>,
+
+    /// Also show each target with the bytes in this order (big, little, word, or pdp)
+    ///
+    /// Takes a list, e.g. --endian little,word.  The value is swapped as a
+    /// --width bit pattern, or as a whole if no width is given.  The swaps
+    /// undo themselves, so this reads a value off the wire as well.
+    #[structopt(long, require_delimiter = true)]
+    endian: Vec<ByteOrder>,
+
+    /// Register layout file (TOML, or JSON if it ends in .json)
+    ///
+    /// Names bit fields, e.g. fields = "EN[0] MODE[3:1] DIV[15:8]", with
+    /// optional named values per field under [values.MODE].  The input is
+    /// shown split into its fields (L).
+    #[structopt(long)]
+    layout: Option<String>,
+
+    /// Set a field of the input number, NAME=VALUE [repeatable, needs --layout]
+    ///
+    /// VALUE is a number (0x, 0o, 0b prefixes allowed) or a value name from
+    /// the layout.  Start from 0 to build a register value from scratch.
+    #[structopt(long, number_of_values = 1)]
+    field: Vec<String>,
+
+    /// Scope waveform preamble, a WFMOutpre? response sent with HEADER ON
+    ///
+    /// Codes are shown as their scaled value (Y), and the samples of a
+    /// --block or -s list at their time (T), from YMULT, YOFF, YZERO, XINCR,
+    /// XZERO, and PT_OFF.  The --ymult style options set one parameter,
+    /// over the preamble's.
+    #[structopt(long)]
+    preamble: Option<String>,
+
+    /// Preamble YMULT: units per code
+    #[structopt(long)]
+    ymult: Option<String>,
+
+    /// Preamble YOFF: the code at YZERO
+    #[structopt(long)]
+    yoff: Option<String>,
+
+    /// Preamble YZERO: value added after scaling
+    #[structopt(long)]
+    yzero: Option<String>,
+
+    /// Preamble XINCR: time between samples
+    #[structopt(long)]
+    xincr: Option<String>,
+
+    /// Preamble XZERO: time of sample PT_OFF
+    #[structopt(long)]
+    xzero: Option<String>,
+
+    /// Preamble PT_OFF: the sample at XZERO
+    #[structopt(long)]
+    pt_off: Option<String>,
+
+    /// ADC or DAC resolution in bits
+    ///
+    /// Codes are shown as their voltage (V), with the size of one code
+    /// (LSB), from --vref and --coding.  With --scaled the input is a
+    /// voltage, shown as its code and the error made (VERR).
+    #[structopt(long, conflicts_with_all = &["text", "float", "q-format", "fourcc"])]
+    adc: Option<u32>,
+
+    /// --adc reference voltage, the full scale or LOW,HIGH
+    ///
+    /// The full scale is 0 to it for straight binary, and - to + it for
+    /// offset binary and two's complement.
+    #[structopt(long, default_value = "1", allow_hyphen_values = true)]
+    vref: String,
+
+    /// --adc coding: straight (binary), offset (binary), or twos (complement)
+    #[structopt(long, default_value = "straight")]
+    coding: Coding,
+
+    /// The input is a scaled value, e.g. volts, to convert back to its code
+    #[structopt(long, conflicts_with_all = &["block", "is-string", "text", "expr", "float", "q-format", "fourcc"])]
+    scaled: bool,
+
+    /// Maximum number of digits after the radix point
+    ///
+    /// Fractions that end or repeat sooner are shown in full, repeating
+    /// digits in parentheses: 0.1 in base 2 is 0.0(0011).
+    #[structopt(long, default_value = "32")]
+    frac_digits: usize,
+
+    /// Rounding for --q (nearest, even, floor, ceil, or zero)
+    #[structopt(long, default_value = "nearest")]
+    rounding: Rounding,
+
+    /// Out of range values for --q (saturate or wrap)
+    #[structopt(long, default_value = "saturate")]
+    overflow: Overflow,
+
+    /// Named set of options from the config file
+    ///
+    /// The config file, ~/.config/numconverter/config.toml, sets defaults
+    /// for every run, named like the :set settings of interactive mode.
+    /// Profiles are sets of them under [profiles.NAME].  Options given on
+    /// the command line win over both.
+    #[structopt(short = "P", long)]
+    profile: Option<String>,
+
+    /// Config file to read instead of ~/.config/numconverter/config.toml
+    #[structopt(long)]
+    config: Option<String>,
+
+    /// Bases to convert to when none are given, set by the config file
+    #[structopt(skip)]
+    default_to: Vec<String>,
+
     /// Char representation of input base (b, o, d, or h) [optional]
     from_base_char: Option<String>,
 
@@ -558,30 +937,6 @@ struct Opt {
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_bin() {
-        assert_eq!(as_string_base(&4,   2).unwrap(), "100");
-        assert_eq!(as_string_base(&12,  2).unwrap(), "1100");
-        assert_eq!(as_string_base(&187, 2).unwrap(), "10111011");
-        assert_eq!(as_string_base(&69,  2).unwrap(), "1000101");
-    }
-
-    #[test]
-    fn test_oct() {
-        assert_eq!(as_string_base(&4,   8).unwrap(), "4");
-        assert_eq!(as_string_base(&12,  8).unwrap(), "14");
-        assert_eq!(as_string_base(&187, 8).unwrap(), "273");
-        assert_eq!(as_string_base(&69,  8).unwrap(), "105");
-    }
-
-    #[test]
-    fn test_hex() {
-        assert_eq!(as_string_base(&4,   16).unwrap(), "4");
-        assert_eq!(as_string_base(&12,  16).unwrap(), "C");
-        assert_eq!(as_string_base(&187, 16).unwrap(), "BB");
-        assert_eq!(as_string_base(&69,  16).unwrap(), "45");
-    }
-
     #[test]
     fn test_get_bases() {
         let mut opt = Opt {
@@ -594,10 +949,51 @@ mod tests {
             silent: false,
             copy: false,
             from_clipboard: false,
+            batch: None,
+            block: None,
+            byt_nr: 1,
+            byt_or: ByteOrder::Big,
+            bn_fmt: SampleFormat::Signed,
+            interactive: false,
             bare: false,
+            format: OutputFormat::Pretty,
             verbosity: 0,
             is_string: false,
+            text: false,
+            utf: None,
+            join: None,
             fourcc: false,
+            fourcc_size: 4,
+            fourcc_endian: ByteOrder::Little,
+            expr: false,
+            width: None,
+            signed_repr: SignedRepr::TwosComplement,
+            float: None,
+            q_format: None,
+            rounding: Rounding::Nearest,
+            overflow: Overflow::Saturate,
+            frac_digits: 32,
+            from_alphabet: None,
+            to_alphabet: Vec::new(),
+            from_encoding: None,
+            from_code: None,
+            endian: Vec::new(),
+            layout: None,
+            field: Vec::new(),
+            preamble: None,
+            ymult: None,
+            yoff: None,
+            yzero: None,
+            xincr: None,
+            xzero: None,
+            pt_off: None,
+            adc: None,
+            vref: "1".to_owned(),
+            coding: Coding::Straight,
+            scaled: false,
+            profile: None,
+            config: None,
+            default_to: Vec::new(),
             from_base_char: Some("b".to_owned()),
             from_num: Some("187".to_owned()),
             to_bases: Vec::new(),
@@ -606,28 +1002,95 @@ mod tests {
         let mut to_bases: Vec<String> = opt.to_bases.clone();
         let res = get_base_and_num(&opt, &mut to_bases).unwrap();
         assert_eq!(res.0, 2);
-        assert_eq!(res.1, Some("187".to_owned()));
+        assert_eq!(res.1, "187".to_owned());
         assert!(to_bases.is_empty());
 
         opt.from_base_char = Some("80".to_owned());
         let res = get_base_and_num(&opt, &mut to_bases).unwrap();
         assert_eq!(res.0, 10);
-        assert_eq!(res.1, Some("80".to_owned()));
+        assert_eq!(res.1, "80".to_owned());
+        assert_eq!(res.2, None);
         assert!(!to_bases.is_empty());
+
+        // The base and width come from the literal itself
+        opt.from_base_char = Some("16'b1010_0000".to_owned());
+        let res = get_base_and_num(&opt, &mut to_bases).unwrap();
+        assert_eq!(res, (2, "10100000".to_owned(), Some(16)));
+
+        // ...unless a base was given
+        opt.from_base = 16;
+        opt.from_base_char = Some("0B1".to_owned());
+        let res = get_base_and_num(&opt, &mut to_bases).unwrap();
+        assert_eq!(res, (16, "0B1".to_owned(), None));
+    }
+
+    #[test]
+    fn test_prefixed_input() {
+        let mut output = Output::default();
+        assert!(convert(&Opt::from_iter(&["numconverter", "4k7"]), &mut output).is_ok());
+        assert_eq!(output.previous, Some(Number::from(4700i64)));
+
+        // E followed by digits is an exponent, not 1.3 exa
+        assert!(convert(&Opt::from_iter(&["numconverter", "1E3"]), &mut Output::default()).is_err());
+        // Only the number itself takes a prefix, not literals in an expression
+        assert!(convert(&Opt::from_iter(&["numconverter", "-e", "4k7 + 1"]), &mut Output::default()).is_err());
+    }
+
+    #[test]
+    fn test_width_limit() {
+        let err = convert(&Opt::from_iter(&["numconverter", "-w", "4000000000", "5"]), &mut Output::default()).unwrap_err();
+        assert_eq!(err.code, ErrorCode::WidthErr);
+        let err = convert(&Opt::from_iter(&["numconverter", "4000000000'hFF"]), &mut Output::default()).unwrap_err();
+        assert_eq!(err.code, ErrorCode::WidthErr);
+        assert!(convert(&Opt::from_iter(&["numconverter", "-w", "64", "5"]), &mut Output::default()).is_ok());
+    }
+
+    #[test]
+    fn test_string_raw() {
+        let mut output = Output::default();
+        let opt = Opt::from_iter(&["numconverter", "-s", "--format", "csv", "h", "4142", "10"]);
+        assert!(convert(&opt, &mut output).is_ok());
+        let raw: Vec<&str> = output.record().rows.iter().map(|row| row.raw.as_str()).collect();
+        assert_eq!(raw, vec!["65 66"]);
     }
 
     #[test]
-    fn test_convert_to_base_10() {
-        assert_eq!(
-            convert_to_base_10("10111011", 2, '_'),
-            Ok(187)
-        );
-        assert_eq!(convert_to_base_10("273", 8,  '_'), Ok(187));
-        assert_eq!(convert_to_base_10("187", 10, '_'), Ok(187));
-        assert_eq!(convert_to_base_10("BB" , 16, '_'), Ok(187));
-        assert_eq!(
-            convert_to_base_10("", 10, '_'),
-            Err(ErrorCode::InputBaseErr)
-        );
+    fn test_batch_opt() {
+        let mut opt = Opt::from_iter(&["numconverter", "--batch", "-", "h", "2", "10"]);
+        let item = batch_opt(&opt, "FF");
+        assert_eq!(item.from_base_char, Some("h".to_owned()));
+        assert_eq!(item.from_num, Some("FF".to_owned()));
+        assert_eq!(item.to_bases, vec!["2".to_owned(), "10".to_owned()]);
+
+        // Without a base char every positional argument is a target
+        opt.from_base_char = Some("2".to_owned());
+        opt.from_num = Some("10".to_owned());
+        opt.to_bases = vec!["16".to_owned()];
+        let item = batch_opt(&opt, "0xFF");
+        assert_eq!(item.from_base_char, None);
+        assert_eq!(item.from_num, Some("0xFF".to_owned()));
+        assert_eq!(item.to_bases, vec!["2".to_owned(), "10".to_owned(), "16".to_owned()]);
+
+        let mut to_bases = item.to_bases.clone();
+        assert_eq!(get_base_and_num(&item, &mut to_bases).unwrap().0, 16);
+        assert_eq!(to_bases, vec!["2".to_owned(), "10".to_owned(), "16".to_owned()]);
+
+        // Hex values that are also base chars are still values
+        for targets in &[&[][..], &["2", "10"][..]] {
+            let args = ["numconverter", "--batch", "-", "-f", "16"].iter().chain(targets.iter());
+            let opt = Opt::from_iter(args);
+            for (value, expected) in &[("b", 11i64), ("d", 13)] {
+                let mut output = Output::default();
+                assert!(convert(&batch_opt(&opt, value), &mut output).is_ok());
+                assert_eq!(output.previous, Some(Number::from(*expected)));
+            }
+        }
+
+        // A base char needs a number after it
+        let err = convert(&Opt::from_iter(&["numconverter", "h"]), &mut Output::default()).unwrap_err();
+        assert_eq!(err.code, ErrorCode::InputBaseErr);
+
+        // Checked the same way as :set byt-nr
+        assert!(Opt::from_iter_safe(&["numconverter", "--byt-nr", "0", "1"]).is_err());
     }
 }
@@ -0,0 +1,262 @@
+////////////////////////////////////////////////////////////////////////////////
+//  Module:   number.rs
+//
+//  Copyright © 2019 Zachary Nielsen
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+////////////////////////////////////////////////////////////////////////////////
+
+////////////////////////////////////////////////////////////////////////////////
+//  Namespaces
+////////////////////////////////////////////////////////////////////////////////
+use crate::{
+    add_separators, alphabet::Alphabet, as_string_base_with, literal, parse_map, signed, Error,
+    ErrorCode,
+};
+use num_bigint::{BigInt, BigUint};
+use num_traits::Signed;
+use std::{collections::HashMap, fmt, str::FromStr};
+
+////////////////////////////////////////////////////////////////////////////////
+//  CODE
+////////////////////////////////////////////////////////////////////////////////
+
+/// A whole number of any size, with its sign
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Number {
+    value: BigInt,
+}
+
+impl Number {
+    pub fn value(&self) -> &BigInt {
+        &self.value
+    }
+
+    /// Reads `text` in `base` (2 to 64), with an optional sign and `_`
+    /// separators
+    pub fn parse(text: &str, base: u32) -> Result<Number, Error> {
+        match Alphabet::standard(base) {
+            Ok(alphabet) => Number::parse_with(text, &alphabet, '_'),
+            Err(e) => Err(Error::new(
+                ErrorCode::InputBaseErr,
+                format!("Error with input base: {}", e),
+            )),
+        }
+    }
+
+    /// Reads `text` in the digits of `alphabet`, skipping `sep_char`
+    pub fn parse_with(text: &str, alphabet: &Alphabet, sep_char: char) -> Result<Number, Error> {
+        signed::convert_signed_to_base_10(text, alphabet, sep_char).map(Number::from)
+    }
+
+    /// The digits in `base` (2 to 64), with a '-' sign if negative
+    pub fn to_string_base(&self, base: u32) -> Result<String, Error> {
+        match Alphabet::standard(base) {
+            Ok(alphabet) => Ok(self.to_string_with(&alphabet)),
+            Err(e) => Err(Error::new(
+                ErrorCode::TargetBaseErr,
+                format!("Error with target base: {}", e),
+            )),
+        }
+    }
+
+    /// The digits in `alphabet`, with a '-' sign if negative
+    pub fn to_string_with(&self, alphabet: &Alphabet) -> String {
+        let digits = as_string_base_with(self.value.magnitude(), alphabet);
+        if self.value.is_negative() {
+            format!("-{}", digits)
+        } else {
+            digits
+        }
+    }
+
+    /// `to_string_base`, with the digits grouped as `separators` says
+    pub fn format(&self, base: u32, separators: &Separators) -> Result<String, Error> {
+        match Alphabet::standard(base) {
+            Ok(alphabet) => Ok(self.format_with(&alphabet, separators)),
+            Err(e) => Err(Error::new(
+                ErrorCode::TargetBaseErr,
+                format!("Error with target base: {}", e),
+            )),
+        }
+    }
+
+    /// `to_string_with`, with the digits grouped as `separators` says
+    pub fn format_with(&self, alphabet: &Alphabet, separators: &Separators) -> String {
+        let digits = as_string_base_with(self.value.magnitude(), alphabet);
+        let grouped = separators.separate(&digits, &alphabet.name);
+        if self.value.is_negative() {
+            format!("-{}", grouped)
+        } else {
+            grouped
+        }
+    }
+}
+
+/// Reads decimal, or a literal that says its base, like 0xFF or 8'hFF
+impl FromStr for Number {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match literal::detect(s) {
+            Some(lit) => Number::parse(&lit.digits, lit.base),
+            None => Number::parse(s.trim(), 10),
+        }
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+impl From<BigInt> for Number {
+    fn from(value: BigInt) -> Self {
+        Number { value }
+    }
+}
+
+impl From<BigUint> for Number {
+    fn from(value: BigUint) -> Self {
+        Number {
+            value: value.into(),
+        }
+    }
+}
+
+impl From<i64> for Number {
+    fn from(value: i64) -> Self {
+        Number {
+            value: value.into(),
+        }
+    }
+}
+
+impl From<u64> for Number {
+    fn from(value: u64) -> Self {
+        Number {
+            value: value.into(),
+        }
+    }
+}
+
+/// How digits are grouped and padded, per base.  Bases are keyed by name,
+/// "16" or an alphabet name like "base58".
+#[derive(Debug, Clone, PartialEq)]
+pub struct Separators {
+    /// Char put between groups
+    pub sep_char: char,
+    /// Digits per group for bases without their own length, 0 for none
+    pub default_length: u32,
+    /// Digits per group, per base
+    pub lengths: HashMap<String, u32>,
+    /// Minimum digits, per base, used for the bytes of strings
+    pub padding: HashMap<String, u32>,
+}
+
+impl Default for Separators {
+    /// Groups of 4 binary, 3 decimal, and 4 hex digits, with bytes padded
+    /// to 8 binary or 2 hex digits
+    fn default() -> Self {
+        let table = |entries: &[(&str, u32)]| {
+            entries
+                .iter()
+                .map(|&(base, len)| (base.to_string(), len))
+                .collect()
+        };
+        Separators {
+            sep_char: ' ',
+            default_length: 0,
+            lengths: table(&[("2", 4), ("10", 3), ("16", 4)]),
+            padding: table(&[("2", 8), ("10", 0), ("16", 2)]),
+        }
+    }
+}
+
+impl Separators {
+    /// Sets group lengths from a map like "10:3,4:4"
+    pub fn parse_sep_map(&mut self, map: &str) -> Result<(), Error> {
+        parse_map(map, &mut self.lengths)
+    }
+
+    /// Sets padding from a map like "16:4"
+    pub fn parse_pad_map(&mut self, map: &str) -> Result<(), Error> {
+        parse_map(map, &mut self.padding)
+    }
+
+    /// Digits per group for `base`
+    pub fn length(&self, base: &str) -> u32 {
+        self.lengths
+            .get(base)
+            .copied()
+            .unwrap_or(self.default_length)
+    }
+
+    /// Puts `sep_char` between each group of `digits`, from the right
+    pub fn separate(&self, digits: &str, base: &str) -> String {
+        add_separators(digits, self.length(base), self.sep_char)
+    }
+
+    /// Pads `digits` with `zero` to the padding of `base`
+    pub fn pad(&self, digits: &str, base: &str, zero: char) -> String {
+        let len = self.padding.get(base).copied().unwrap_or(0) as usize;
+        let count = digits.chars().count();
+        let mut padded: String = std::iter::repeat_n(zero, len.saturating_sub(count)).collect();
+        padded.push_str(digits);
+        padded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Number::parse("-FF", 16), Ok(Number::from(-255i64)));
+        assert_eq!(Number::parse("1010_0000", 2), Ok(Number::from(160u64)));
+        assert_eq!("0xBB".parse(), Ok(Number::from(187u64)));
+        assert_eq!("8'hFF".parse(), Ok(Number::from(255u64)));
+        assert_eq!("-42".parse(), Ok(Number::from(-42i64)));
+        assert!(Number::parse("G", 16).is_err());
+        assert!(Number::parse("1", 65).is_err());
+    }
+
+    #[test]
+    fn test_format() {
+        let num = Number::from(-0xBEEFi64);
+        let separators = Separators::default();
+        assert_eq!(num.to_string_base(16), Ok(String::from("-BEEF")));
+        assert_eq!(
+            num.format(2, &separators),
+            Ok(String::from("-1011 1110 1110 1111"))
+        );
+        assert_eq!(num.format(10, &separators), Ok(String::from("-48 879")));
+        assert_eq!(num.to_string(), "-48879");
+
+        let base58 = Alphabet::named("base58").unwrap();
+        assert_eq!(Number::from(10_000u64).to_string_with(&base58), "3yR");
+    }
+
+    #[test]
+    fn test_separators() {
+        let mut separators = Separators {
+            sep_char: '_',
+            ..Separators::default()
+        };
+        assert_eq!(separators.parse_sep_map("16:2,8:3"), Ok(()));
+        assert_eq!(separators.separate("DEADBEEF", "16"), "DE_AD_BE_EF");
+        assert_eq!(separators.separate("1777", "8"), "1_777");
+        assert_eq!(separators.separate("zz", "36"), "zz");
+
+        assert_eq!(separators.parse_pad_map("16:4"), Ok(()));
+        assert_eq!(separators.pad("F", "16", '0'), "000F");
+        assert_eq!(separators.pad("101", "2", '0'), "00000101");
+        assert_eq!(separators.pad("12345", "16", '0'), "12345");
+    }
+}
@@ -0,0 +1,245 @@
+////////////////////////////////////////////////////////////////////////////////
+//  Module:   output.rs
+//
+//  Copyright © 2019 Zachary Nielsen
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+////////////////////////////////////////////////////////////////////////////////
+
+////////////////////////////////////////////////////////////////////////////////
+//  Namespaces
+////////////////////////////////////////////////////////////////////////////////
+use serde::Serialize;
+use std::str::FromStr;
+
+////////////////////////////////////////////////////////////////////////////////
+//  CODE
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    /// The usual "Base NN: value" lines
+    Pretty,
+    Json,
+    Csv,
+    Toml,
+    Yaml,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "pretty" | "text" => Ok(OutputFormat::Pretty),
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            "toml" => Ok(OutputFormat::Toml),
+            "yaml" | "yml" => Ok(OutputFormat::Yaml),
+            _ => Err(format!(
+                "Unknown format '{}'. Use pretty, json, csv, toml, or yaml",
+                s
+            )),
+        }
+    }
+}
+
+/// One converted value
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Row {
+    /// Name of the row, e.g. "16", "ASCII", or "16 LE"
+    pub base: String,
+    /// The value as it is printed, with separators
+    pub value: String,
+    /// The value without separators
+    pub raw: String,
+}
+
+/// Everything one conversion produced, for the structured formats.  Fields
+/// that hold tables come last, which TOML needs.
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct Record {
+    /// Line of the --batch input the number was on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    /// The number as given
+    pub input: String,
+    /// Base the input was read in
+    pub input_base: String,
+    /// Bit width in effect, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    pub errors: Vec<String>,
+    /// Things done to make the input fit, like clipping to a range
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    pub rows: Vec<Row>,
+}
+
+/// Row of the CSV output, the record flattened
+#[derive(Serialize)]
+struct CsvLine<'a> {
+    line: Option<usize>,
+    input: &'a str,
+    input_base: &'a str,
+    width: Option<u32>,
+    base: &'a str,
+    value: &'a str,
+    raw: &'a str,
+    error: &'a str,
+}
+
+/// Many records, as TOML has no top level arrays
+#[derive(Serialize)]
+struct Batch<'a> {
+    records: &'a [Record],
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   render
+//
+// NOTES:
+//     Writes a record in a structured format.  CSV has one line per row,
+//     then one per error, each repeating the input columns.
+// ARGS:
+//     record - the conversion results
+//     format - the format to write, not Pretty
+// RETURN: The text, or an error from the serializer
+//
+pub fn render(record: &Record, format: OutputFormat) -> Result<String, String> {
+    match format {
+        OutputFormat::Pretty => Err(String::from("Pretty output is printed row by row")),
+        OutputFormat::Json => serde_json::to_string_pretty(record)
+            .map(|s| s + "\n")
+            .map_err(|e| e.to_string()),
+        OutputFormat::Toml => toml::to_string(record).map_err(|e| e.to_string()),
+        OutputFormat::Yaml => serde_yaml::to_string(record).map_err(|e| e.to_string()),
+        OutputFormat::Csv => write_csv(std::slice::from_ref(record)),
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   render_batch
+//
+// NOTES:
+//     `render` for the records of a --batch run: a list in JSON and YAML,
+//     [[records]] tables in TOML, and one CSV table for all of them.
+// ARGS:
+//     records - the conversion results, one per input
+//     format - the format to write, not Pretty
+// RETURN: The text, or an error from the serializer
+//
+pub fn render_batch(records: &[Record], format: OutputFormat) -> Result<String, String> {
+    match format {
+        OutputFormat::Pretty => Err(String::from("Pretty output is printed row by row")),
+        OutputFormat::Json => serde_json::to_string_pretty(records)
+            .map(|s| s + "\n")
+            .map_err(|e| e.to_string()),
+        OutputFormat::Toml => toml::to_string(&Batch { records }).map_err(|e| e.to_string()),
+        OutputFormat::Yaml => serde_yaml::to_string(records).map_err(|e| e.to_string()),
+        OutputFormat::Csv => write_csv(records),
+    }
+}
+
+/// Writes the records as one CSV table, with a header
+fn write_csv(records: &[Record]) -> Result<String, String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for record in records {
+        let line = |base, value, raw, error| CsvLine {
+            line: record.line,
+            input: &record.input,
+            input_base: &record.input_base,
+            width: record.width,
+            base,
+            value,
+            raw,
+            error,
+        };
+        for row in &record.rows {
+            writer
+                .serialize(line(&row.base, &row.value, &row.raw, ""))
+                .map_err(|e| e.to_string())?;
+        }
+        for error in &record.errors {
+            writer
+                .serialize(line("", "", "", error))
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    let bytes = writer.into_inner().map_err(|e| e.to_string())?;
+    String::from_utf8(bytes).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record() -> Record {
+        Record {
+            line: None,
+            input: String::from("0xBB"),
+            input_base: String::from("16"),
+            width: None,
+            errors: vec![String::from("Target Base Error")],
+            warnings: Vec::new(),
+            rows: vec![Row {
+                base: String::from("2"),
+                value: String::from("1011 1011"),
+                raw: String::from("10111011"),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_json() {
+        let text = render(&record(), OutputFormat::Json).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(value["input_base"], "16");
+        assert_eq!(value["rows"][0]["raw"], "10111011");
+        assert!(value.get("width").is_none());
+    }
+
+    #[test]
+    fn test_csv() {
+        let text = render(&record(), OutputFormat::Csv).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "line,input,input_base,width,base,value,raw,error");
+        assert_eq!(lines[1], ",0xBB,16,,2,1011 1011,10111011,");
+        assert_eq!(lines[2], ",0xBB,16,,,,,Target Base Error");
+    }
+
+    #[test]
+    fn test_toml_yaml() {
+        let text = render(&record(), OutputFormat::Toml).unwrap();
+        assert!(text.starts_with("input = \"0xBB\"\n"));
+        assert!(text.contains("[[rows]]\nbase = \"2\"\n"));
+
+        let text = render(&record(), OutputFormat::Yaml).unwrap();
+        assert!(text.contains("- base: '2'\n"));
+        assert!(render(&record(), OutputFormat::Pretty).is_err());
+    }
+
+    #[test]
+    fn test_batch() {
+        let mut second = record();
+        second.line = Some(2);
+        second.errors.clear();
+        let records = vec![record(), second];
+
+        let text = render_batch(&records, OutputFormat::Csv).unwrap();
+        assert_eq!(text.lines().count(), 4);
+        assert!(text.ends_with("\n2,0xBB,16,,2,1011 1011,10111011,\n"));
+
+        let text = render_batch(&records, OutputFormat::Json).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(value[1]["line"], 2);
+
+        let text = render_batch(&records, OutputFormat::Toml).unwrap();
+        assert_eq!(text.matches("[[records]]").count(), 2);
+        assert!(text.contains("[[records.rows]]"));
+    }
+}
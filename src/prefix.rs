@@ -0,0 +1,191 @@
+////////////////////////////////////////////////////////////////////////////////
+//  Module:   prefix.rs
+//
+//  Copyright © 2019 Zachary Nielsen
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+////////////////////////////////////////////////////////////////////////////////
+
+////////////////////////////////////////////////////////////////////////////////
+//  Namespaces
+////////////////////////////////////////////////////////////////////////////////
+use crate::decimal::{exponent, format_significant, parse_decimal, pow10, pow2};
+use num_rational::BigRational;
+use num_traits::{Signed, Zero};
+
+////////////////////////////////////////////////////////////////////////////////
+//  CODE
+////////////////////////////////////////////////////////////////////////////////
+
+/// SI prefixes and their power of 10.  u stands in for µ, and K for k.
+const SI: &[(&str, i32)] = &[
+    ("y", -24),
+    ("z", -21),
+    ("a", -18),
+    ("f", -15),
+    ("p", -12),
+    ("n", -9),
+    ("µ", -6),
+    ("u", -6),
+    ("m", -3),
+    ("k", 3),
+    ("K", 3),
+    ("M", 6),
+    ("G", 9),
+    ("T", 12),
+    ("P", 15),
+    ("E", 18),
+    ("Z", 21),
+    ("Y", 24),
+];
+
+/// IEC binary prefixes and their power of 2
+const IEC: &[(&str, i64)] = &[
+    ("Ki", 10),
+    ("Mi", 20),
+    ("Gi", 30),
+    ("Ti", 40),
+    ("Pi", 50),
+    ("Ei", 60),
+    ("Zi", 70),
+    ("Yi", 80),
+];
+
+/// Significant digits written
+const DIGITS: usize = 12;
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   parse
+//
+// NOTES:
+//     Reads a decimal number with an SI or IEC prefix, like 1.5M, 64Ki, or
+//     2GiB (a trailing B for bytes is dropped).  The prefix may also stand
+//     for the radix point, as in 4k7 for 4700, except E, so that 1E3 is not
+//     taken for 1.3 exa.
+// ARGS:
+//     text - the number to read
+// RETURN: The exact value, or None if `text` is not a prefixed number
+//
+pub fn parse(text: &str) -> Option<BigRational> {
+    let text = text.trim();
+    let (negative, unsigned) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let unsigned = unsigned
+        .strip_suffix('B')
+        .filter(|rest| !rest.is_empty())
+        .unwrap_or(unsigned);
+
+    let idx = unsigned.find(|c: char| !c.is_ascii_digit() && c != '.')?;
+    let (mantissa, rest) = unsigned.split_at(idx);
+    let (scale, prefix) =
+        if let Some(&(prefix, bits)) = IEC.iter().find(|(p, _)| rest.starts_with(p)) {
+            (pow2(bits), prefix)
+        } else {
+            let &(prefix, exp) = SI.iter().find(|(p, _)| rest.starts_with(p))?;
+            (pow10(exp), prefix)
+        };
+    let after = &rest[prefix.len()..];
+
+    // Digits after the prefix are the fraction, with no other radix point
+    let value = if after.is_empty() {
+        parse_decimal(mantissa)?
+    } else if after.chars().all(|c| c.is_ascii_digit()) && !mantissa.contains('.') && prefix != "E"
+    {
+        parse_decimal(&format!("{}.{}", mantissa, after))?
+    } else {
+        return None;
+    };
+    let value = value * scale;
+    Some(if negative { -value } else { value })
+}
+
+/// The power of 10 for engineering notation, a multiple of 3
+fn eng_exponent(value: &BigRational) -> i32 {
+    if value.is_zero() {
+        return 0;
+    }
+    exponent(&value.abs()).div_euclid(3) * 3
+}
+
+/// Writes `value` scaled down by 10^`exp`
+fn scaled(value: &BigRational, exp: i32) -> String {
+    format_significant(&(value / pow10(exp)), DIGITS)
+}
+
+/// Engineering notation, a power of 10 that is a multiple of 3, e.g. 4.7e3
+pub fn format_eng(value: &BigRational) -> String {
+    match eng_exponent(value) {
+        0 => scaled(value, 0),
+        exp => format!("{}e{}", scaled(value, exp), exp),
+    }
+}
+
+/// With an SI prefix, e.g. 4.7 k
+pub fn format_si(value: &BigRational) -> String {
+    let exp = eng_exponent(value).clamp(-24, 24);
+    match SI.iter().find(|(_, e)| *e == exp) {
+        Some((prefix, _)) => format!("{} {}", scaled(value, exp), prefix),
+        None => scaled(value, 0),
+    }
+}
+
+/// A byte count with an IEC prefix, e.g. 256 KiB
+pub fn format_iec(value: &BigRational) -> String {
+    let magnitude = value.abs();
+    match IEC.iter().rev().find(|(_, bits)| magnitude >= pow2(*bits)) {
+        Some((prefix, bits)) => format!(
+            "{} {}B",
+            format_significant(&(value / pow2(*bits)), DIGITS),
+            prefix
+        ),
+        None => format!("{} B", format_significant(value, DIGITS)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_bigint::BigInt;
+
+    fn whole(value: i64) -> BigRational {
+        BigRational::from_integer(BigInt::from(value))
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("4k7"), Some(whole(4700)));
+        assert_eq!(parse("1.5M"), Some(whole(1_500_000)));
+        assert_eq!(parse("64Ki"), Some(whole(65536)));
+        assert_eq!(parse("2GiB"), Some(whole(2 << 30)));
+        assert_eq!(parse("-3K"), Some(whole(-3000)));
+        assert_eq!(parse("10u"), parse_decimal("0.00001"));
+        assert_eq!(parse("2.2µ"), parse_decimal("0.0000022"));
+        assert_eq!(parse("1000"), None);
+        assert_eq!(parse("1.5e3"), None);
+        assert_eq!(parse("4.7k7"), None);
+        assert_eq!(parse("12q"), None);
+        assert_eq!(parse("k"), None);
+        assert_eq!(parse("1E3"), None);
+        assert_eq!(parse("1E"), Some(whole(1_000_000_000_000_000_000)));
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(format_eng(&whole(4700)), "4.7e3");
+        assert_eq!(format_eng(&whole(-262_144)), "-262.144e3");
+        assert_eq!(format_eng(&parse_decimal("0.00001").unwrap()), "10e-6");
+        assert_eq!(format_eng(&whole(12)), "12");
+        assert_eq!(format_si(&whole(4700)), "4.7 k");
+        assert_eq!(format_si(&parse_decimal("0.0000022").unwrap()), "2.2 µ");
+        assert_eq!(format_si(&whole(0)), "0");
+        assert_eq!(format_iec(&whole(0x40000)), "256 KiB");
+        assert_eq!(format_iec(&whole(3 << 29)), "1.5 GiB");
+        assert_eq!(format_iec(&whole(1000)), "1000 B");
+    }
+}
@@ -0,0 +1,507 @@
+////////////////////////////////////////////////////////////////////////////////
+//  Module:   repl.rs
+//
+//  Copyright © 2019 Zachary Nielsen
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+////////////////////////////////////////////////////////////////////////////////
+
+////////////////////////////////////////////////////////////////////////////////
+//  Namespaces
+////////////////////////////////////////////////////////////////////////////////
+use crate::{convert, Opt, Output};
+use numconverter::{
+    adc::Adc,
+    alphabet::Alphabet,
+    block, get_from_base,
+    output::{self, OutputFormat},
+    waveform::Preamble,
+    Number,
+};
+use rustyline::{error::ReadlineError, Editor};
+use std::{fmt::Display, path::PathBuf, str::FromStr};
+
+////////////////////////////////////////////////////////////////////////////////
+//  CODE
+////////////////////////////////////////////////////////////////////////////////
+
+const HELP: &str = "\
+Enter a number to convert it, optionally followed by bases for that line.
+`_` is the previous result.
+
+:set NAME VALUE   change a setting, e.g. :set base 16, :set to 2 10 16
+:set              show the settings
+:help             show this help
+:quit             leave (or Ctrl-D)
+
+Settings: base, to, alphabet, sep-map, pad-map, sep-length, sep-char,
+no-sep, bare, expr, str, text, utf, join, fourcc, fourcc-size,
+fourcc-endian, byt-nr, byt-or, bn-fmt, width, signed-repr, float, q, endian,
+layout, preamble, ymult, yoff, yzero, xincr, xzero, pt-off, adc, vref, coding,
+scaled, from-code, format";
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   run
+//
+// NOTES:
+//     Reads numbers from the terminal until Ctrl-D, converting each with the
+//     current settings.  The command line options are the first settings,
+//     with the positional arguments taken as the base char and targets.
+// ARGS:
+//     opt - command line options
+//
+pub fn run(opt: &Opt) {
+    let mut state = initial_state(opt);
+    let mut previous: Option<Number> = None;
+
+    let mut editor = Editor::<()>::new();
+    let history = history_path();
+    if let Some(path) = &history {
+        // There is no history the first time round
+        let _ = editor.load_history(path);
+    }
+    println!("numconverter interactive mode, :help for commands");
+
+    loop {
+        let line = match editor.readline("> ") {
+            Ok(v) => v,
+            Err(ReadlineError::Interrupted) | Err(ReadlineError::Eof) => break,
+            Err(e) => {
+                eprintln!("Could not read input:\n\t{}", e);
+                break;
+            }
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        editor.add_history_entry(line);
+
+        if let Some(command) = line.strip_prefix(':') {
+            let mut words = command.split_whitespace();
+            match words.next().unwrap_or("") {
+                "q" | "quit" | "exit" => break,
+                "h" | "help" => println!("{}", HELP),
+                "set" => {
+                    let name = words.next();
+                    let value: Vec<&str> = words.collect();
+                    match name {
+                        None => show(&state),
+                        Some(name) => {
+                            if let Err(e) = set(&mut state, name, &value) {
+                                eprintln!("Error: {}", e);
+                            }
+                        }
+                    }
+                }
+                other => eprintln!("Unknown command :{}, :help for commands", other),
+            }
+            continue;
+        }
+
+        let line = match &previous {
+            Some(value) => substitute_previous(line, &previous_digits(&state, value)),
+            None if has_previous_ref(line) => {
+                eprintln!("There is no previous result yet");
+                continue;
+            }
+            None => line.to_string(),
+        };
+        if let Some(value) = convert_line(&state, &line) {
+            previous = Some(value);
+        }
+    }
+
+    if let Some(path) = &history {
+        if let Err(e) = editor.save_history(path) {
+            eprintln!("Could not save history to {}:\n\t{}", path.display(), e);
+        }
+    }
+}
+
+/// Converts one line of input, returning the value to use for `_`
+fn convert_line(state: &Opt, line: &str) -> Option<Number> {
+    // Strings and expressions have spaces in them, so the line is one value
+    let (value, bases) = if state.expr || state.is_string || state.text {
+        (line, Vec::new())
+    } else {
+        let mut words = line.split_whitespace();
+        let value = words.next().unwrap_or("");
+        (value, words.map(|w| w.to_string()).collect())
+    };
+
+    // The value is in the session's base, even one like b that is also a
+    // base char, and the words after it are all targets
+    let mut line_state = state.clone();
+    line_state.from_base_char = None;
+    line_state.from_num = Some(value.to_string());
+    if !bases.is_empty() {
+        line_state.to_bases = bases;
+    }
+
+    let mut output = Output::default();
+    let result = convert(&line_state, &mut output);
+    if let Err(err) = &result {
+        eprintln!("Error: {}", err);
+    }
+    if state.format != OutputFormat::Pretty {
+        let record = output.record();
+        if let Err(err) = result {
+            record.errors.push(err.message);
+        }
+        match output::render(record, state.format) {
+            Ok(text) => print!("{}", text),
+            Err(e) => eprintln!("Could not write {:?} output:\n\t{}", state.format, e),
+        }
+    }
+    output.previous
+}
+
+/// The command line options, with the positional arguments made settings
+fn initial_state(opt: &Opt) -> Opt {
+    let mut state = opt.clone();
+    let mut bases: Vec<String> = opt
+        .from_base_char
+        .iter()
+        .chain(opt.from_num.iter())
+        .chain(opt.to_bases.iter())
+        .cloned()
+        .collect();
+    if let Some(base) = bases.first().and_then(|b| get_from_base(b)) {
+        state.from_base = base;
+        bases.remove(0);
+    }
+    state.from_base_char = None;
+    state.from_num = None;
+    state.to_bases = bases;
+    state.interactive = false;
+    state.copy = false;
+    state
+}
+
+/// History file in the home directory
+fn history_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".numconverter_history"))
+}
+
+/// Prints the settings that differ between conversions
+fn show(state: &Opt) {
+    let or_none = |v: Option<String>| v.unwrap_or_else(|| String::from("none"));
+    println!("base        {}", state.from_base);
+    println!(
+        "alphabet    {}",
+        or_none(state.from_alphabet.as_ref().map(|a| a.name.clone()))
+    );
+    println!("to          {}", state.to_bases.join(" "));
+    println!("sep-map     {}", state.sep_map);
+    println!("pad-map     {}", state.pad_map);
+    println!("sep-length  {}", state.sep_length);
+    println!("sep-char    '{}'", state.sep_char);
+    println!("no-sep      {}", state.no_sep);
+    println!("bare        {}", state.bare);
+    println!("expr        {}", state.expr);
+    println!("str         {}", state.is_string);
+    println!("text        {}", state.text);
+    println!(
+        "utf         {}",
+        or_none(state.utf.map(|u| u.name().to_string()))
+    );
+    println!(
+        "join        {}",
+        or_none(state.join.map(|o| o.label().to_string()))
+    );
+    println!("fourcc      {}", state.fourcc);
+    println!("fourcc-size {}", state.fourcc_size);
+    println!("fourcc-endian {}", state.fourcc_endian.label());
+    println!("byt-nr      {}", state.byt_nr);
+    println!("byt-or      {}", state.byt_or.label());
+    println!("bn-fmt      {:?}", state.bn_fmt);
+    println!(
+        "width       {}",
+        or_none(state.width.map(|w| w.to_string()))
+    );
+    println!("signed-repr {:?}", state.signed_repr);
+    println!(
+        "float       {}",
+        or_none(state.float.map(|f| f.name.to_string()))
+    );
+    println!(
+        "q           {}",
+        or_none(state.q_format.map(|f| f.to_string()))
+    );
+    println!(
+        "endian      {}",
+        state
+            .endian
+            .iter()
+            .map(|o| o.label())
+            .collect::<Vec<&str>>()
+            .join(",")
+    );
+    println!("layout      {}", or_none(state.layout.clone()));
+    println!("preamble    {}", or_none(state.preamble.clone()));
+    println!("ymult       {}", or_none(state.ymult.clone()));
+    println!("yoff        {}", or_none(state.yoff.clone()));
+    println!("yzero       {}", or_none(state.yzero.clone()));
+    println!("xincr       {}", or_none(state.xincr.clone()));
+    println!("xzero       {}", or_none(state.xzero.clone()));
+    println!("pt-off      {}", or_none(state.pt_off.clone()));
+    println!("adc         {}", or_none(state.adc.map(|b| b.to_string())));
+    println!("vref        {}", state.vref);
+    println!("coding      {:?}", state.coding);
+    println!("scaled      {}", state.scaled);
+    println!(
+        "from-code   {}",
+        or_none(state.from_code.map(|c| c.to_string()))
+    );
+    println!("format      {:?}", state.format);
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   set
+//
+// NOTES:
+//     Changes one setting.  Options that can be turned off take "none" or
+//     "off", flags take on/off.
+// ARGS:
+//     state - the settings
+//     name - setting to change, named like its command line option
+//     value - the words after the name
+// RETURN: Nothing, or why the value was not taken
+//
+pub fn set(state: &mut Opt, name: &str, value: &[&str]) -> Result<(), String> {
+    let text = value.join(" ");
+    let off = text.is_empty() || text == "none" || text == "off";
+    match name {
+        "base" | "from" | "from-base" => {
+            let base = match get_from_base(&text) {
+                Some(v) => v,
+                None => parse::<u32>(&text)?,
+            };
+            Alphabet::standard(base)?;
+            state.from_base = base;
+            state.from_alphabet = None;
+        }
+        "to" => state.to_bases = value.iter().map(|v| v.to_string()).collect(),
+        "alphabet" | "from-alphabet" => {
+            state.from_alphabet = if off { None } else { Some(parse(&text)?) }
+        }
+        "sep-map" => state.sep_map = text,
+        "pad-map" => state.pad_map = text,
+        "sep-length" => state.sep_length = parse(&text)?,
+        "sep-char" => {
+            // Quotes let a space be given
+            let text = text.trim_matches('\'');
+            state.sep_char = if text.is_empty() { ' ' } else { parse(text)? };
+        }
+        "no-sep" => state.no_sep = parse_flag(&text)?,
+        "bare" => state.bare = parse_flag(&text)?,
+        "expr" => state.expr = parse_flag(&text)?,
+        "str" => state.is_string = parse_flag(&text)?,
+        "text" => state.text = parse_flag(&text)?,
+        "utf" => state.utf = if off { None } else { Some(parse(&text)?) },
+        "join" => state.join = if off { None } else { Some(parse(&text)?) },
+        "fourcc" => state.fourcc = parse_flag(&text)?,
+        "fourcc-size" => {
+            state.fourcc_size = match parse(&text)? {
+                size @ (2 | 4 | 8) => size,
+                _ => return Err(String::from("A code is 2, 4, or 8 bytes")),
+            }
+        }
+        "fourcc-endian" => state.fourcc_endian = parse(&text)?,
+        "byt-nr" => state.byt_nr = block::sample_width(&text)?,
+        "byt-or" => state.byt_or = parse(&text)?,
+        "bn-fmt" => state.bn_fmt = parse(&text)?,
+        "width" => state.width = if off { None } else { Some(parse(&text)?) },
+        "signed-repr" => state.signed_repr = parse(&text)?,
+        "float" => state.float = if off { None } else { Some(parse(&text)?) },
+        "q" => state.q_format = if off { None } else { Some(parse(&text)?) },
+        "endian" => {
+            state.endian = if off {
+                Vec::new()
+            } else {
+                text.split(',')
+                    .map(|o| parse(o.trim()))
+                    .collect::<Result<_, _>>()?
+            }
+        }
+        "layout" => state.layout = if off { None } else { Some(text) },
+        "preamble" => {
+            state.preamble = if off {
+                None
+            } else {
+                text.parse::<Preamble>()?;
+                Some(text)
+            }
+        }
+        "ymult" | "yoff" | "yzero" | "xincr" | "xzero" | "pt-off" => {
+            let value = if off {
+                None
+            } else {
+                Preamble::default().set(&name.replace('-', "_"), &text)?;
+                Some(text)
+            };
+            match name {
+                "ymult" => state.ymult = value,
+                "yoff" => state.yoff = value,
+                "yzero" => state.yzero = value,
+                "xincr" => state.xincr = value,
+                "xzero" => state.xzero = value,
+                _ => state.pt_off = value,
+            }
+        }
+        "adc" => state.adc = if off { None } else { Some(parse(&text)?) },
+        "vref" => {
+            Adc::new(1, &text, state.coding)?;
+            state.vref = text;
+        }
+        "coding" => state.coding = parse(&text)?,
+        "scaled" => state.scaled = parse_flag(&text)?,
+        "from-code" => state.from_code = if off { None } else { Some(parse(&text)?) },
+        "format" => state.format = parse(&text)?,
+        _ => return Err(format!("Unknown setting {}, :help for the list", name)),
+    }
+    Ok(())
+}
+
+/// Reads a setting value, with the option's own error message
+fn parse<T>(text: &str) -> Result<T, String>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    text.parse::<T>()
+        .map_err(|e| format!("Could not read '{}': {}", text, e))
+}
+
+/// Reads on/off, true/false, or yes/no
+fn parse_flag(text: &str) -> Result<bool, String> {
+    match text {
+        "" | "on" | "true" | "yes" => Ok(true),
+        "off" | "false" | "no" => Ok(false),
+        _ => Err(format!("Expected on or off, got '{}'", text)),
+    }
+}
+
+/// The previous result, written in the current input base
+fn previous_digits(state: &Opt, value: &Number) -> String {
+    let alphabet = state
+        .from_alphabet
+        .clone()
+        .or_else(|| Alphabet::standard(state.from_base).ok())
+        .unwrap_or_else(|| Alphabet::standard(10).expect("base 10"));
+    value.to_string_with(&alphabet)
+}
+
+/// Whether `c` can be part of a number, so an `_` next to it is a separator
+fn is_word(c: Option<char>) -> bool {
+    c.is_some_and(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Whether the line uses `_`
+fn has_previous_ref(line: &str) -> bool {
+    substitute_previous(line, "") != line
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   substitute_previous
+//
+// NOTES:
+//     Replaces each `_` that stands on its own with the previous result.
+//     An `_` between digits, as in 1010_0000, is left as a separator.
+// ARGS:
+//     line - the line as typed
+//     previous - the previous result, in the input base
+// RETURN: The line to convert
+//
+fn substitute_previous(line: &str, previous: &str) -> String {
+    let chars: Vec<char> = line.chars().collect();
+    let mut out = String::new();
+    for (idx, &c) in chars.iter().enumerate() {
+        let before = if idx == 0 { None } else { Some(chars[idx - 1]) };
+        let after = chars.get(idx + 1).copied();
+        if c == '_' && !is_word(before) && !is_word(after) {
+            out.push_str(previous);
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use structopt::StructOpt;
+
+    fn opt(args: &[&str]) -> Opt {
+        let mut all = vec!["numconverter", "-i"];
+        all.extend_from_slice(args);
+        initial_state(&Opt::from_iter(&all))
+    }
+
+    #[test]
+    fn test_initial_state() {
+        let state = opt(&["h", "2", "10"]);
+        assert_eq!(state.from_base, 16);
+        assert_eq!(state.to_bases, vec!["2".to_owned(), "10".to_owned()]);
+        assert_eq!(state.from_base_char, None);
+
+        let state = opt(&["-f", "8", "16"]);
+        assert_eq!(state.from_base, 8);
+        assert_eq!(state.to_bases, vec!["16".to_owned()]);
+    }
+
+    #[test]
+    fn test_convert_line() {
+        let mut state = opt(&[]);
+        assert_eq!(set(&mut state, "base", &["16"]), Ok(()));
+        assert_eq!(convert_line(&state, "b"), Some(Number::from(11i64)));
+        assert_eq!(convert_line(&state, "d 2"), Some(Number::from(13i64)));
+        assert_eq!(convert_line(&state, "g"), None);
+    }
+
+    #[test]
+    fn test_set() {
+        let mut state = opt(&[]);
+        assert_eq!(set(&mut state, "base", &["h"]), Ok(()));
+        assert_eq!(state.from_base, 16);
+        assert_eq!(set(&mut state, "to", &["2", "base58"]), Ok(()));
+        assert_eq!(state.to_bases, vec!["2".to_owned(), "base58".to_owned()]);
+        assert_eq!(set(&mut state, "width", &["8"]), Ok(()));
+        assert_eq!(state.width, Some(8));
+        assert_eq!(set(&mut state, "width", &["none"]), Ok(()));
+        assert_eq!(state.width, None);
+        assert_eq!(set(&mut state, "sep-char", &["'", "'"]), Ok(()));
+        assert_eq!(state.sep_char, ' ');
+        assert_eq!(set(&mut state, "bare", &[]), Ok(()));
+        assert!(state.bare);
+        assert_eq!(set(&mut state, "endian", &["le,word"]), Ok(()));
+        assert_eq!(state.endian.len(), 2);
+
+        assert!(set(&mut state, "base", &["65"]).is_err());
+        assert!(set(&mut state, "width", &["eight"]).is_err());
+        assert!(set(&mut state, "colour", &["red"]).is_err());
+        assert!(set(&mut state, "byt-nr", &["0"]).is_err());
+        assert_eq!(state.width, None);
+    }
+
+    #[test]
+    fn test_previous() {
+        assert_eq!(substitute_previous("_", "FF"), "FF");
+        assert_eq!(substitute_previous("_ << 4", "FF"), "FF << 4");
+        assert_eq!(substitute_previous("(_+_)", "2"), "(2+2)");
+        assert_eq!(substitute_previous("1010_0000", "FF"), "1010_0000");
+        assert_eq!(substitute_previous("_x", "FF"), "_x");
+        assert!(has_previous_ref("_ 2"));
+        assert!(!has_previous_ref("1_000"));
+
+        let mut state = opt(&[]);
+        state.from_base = 16;
+        assert_eq!(previous_digits(&state, &Number::from(-255i64)), "-FF");
+    }
+}
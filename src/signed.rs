@@ -0,0 +1,259 @@
+////////////////////////////////////////////////////////////////////////////////
+//  Module:   signed.rs
+//
+//  Copyright © 2019 Zachary Nielsen
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+////////////////////////////////////////////////////////////////////////////////
+
+////////////////////////////////////////////////////////////////////////////////
+//  Namespaces
+////////////////////////////////////////////////////////////////////////////////
+use crate::{alphabet::Alphabet, convert_to_base_10_with, Error, ErrorCode};
+use num_bigint::{BigInt, BigUint, Sign};
+use num_traits::{One, Signed, Zero};
+use std::str::FromStr;
+
+////////////////////////////////////////////////////////////////////////////////
+//  CODE
+////////////////////////////////////////////////////////////////////////////////
+
+/// How a negative number is laid out in a fixed number of bits
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SignedRepr {
+    TwosComplement,
+    OnesComplement,
+    SignMagnitude,
+}
+
+impl FromStr for SignedRepr {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "twos" | "2s" => Ok(SignedRepr::TwosComplement),
+            "ones" | "1s" => Ok(SignedRepr::OnesComplement),
+            "sign-mag" | "sm" => Ok(SignedRepr::SignMagnitude),
+            _ => Err(format!(
+                "Unknown signed representation '{}'. Use twos, ones, or sign-mag",
+                s
+            )),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   convert_signed_to_base_10
+//
+// NOTES:  Same as `convert_to_base_10`, but allows a single leading '-'
+// ARGS:
+//     from_num - String representation of a number in `alphabet`
+//     alphabet - Digits of the base the input number is given in
+//     sep_char - Separator char to strip before converting
+// RETURN: The signed value, or an error
+//
+pub fn convert_signed_to_base_10(
+    from_num: &str,
+    alphabet: &Alphabet,
+    sep_char: char,
+) -> Result<BigInt, Error> {
+    match from_num.strip_prefix('-') {
+        // Point errors in the digits at the text as given, sign and all
+        Some(magnitude) if !alphabet.contains('-') => {
+            convert_to_base_10_with(magnitude, alphabet, sep_char)
+                .map(|v| -BigInt::from(v))
+                .map_err(|e| e.within(from_num, 1))
+        }
+        _ => Ok(BigInt::from(convert_to_base_10_with(
+            from_num, alphabet, sep_char,
+        )?)),
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   encode
+//
+// NOTES:
+//     Lays `value` out as a `width` bit pattern.  Non-negative values are
+//     accepted up to the unsigned maximum for the width, negative values
+//     must fit in the signed range of `repr`.
+// ARGS:
+//     value - number to encode
+//     width - number of bits in the pattern
+//     repr - representation to use for negative numbers
+// RETURN: The bit pattern, or an error if the value does not fit
+//
+pub fn encode(value: &BigInt, width: u32, repr: SignedRepr) -> Result<BigUint, Error> {
+    if width == 0 {
+        return Err(Error::new(
+            ErrorCode::WidthErr,
+            "Width must be at least 1 bit",
+        ));
+    }
+
+    let modulus = BigUint::one() << width;
+    let magnitude = value.magnitude();
+
+    if !value.is_negative() {
+        if *magnitude >= modulus {
+            return Err(Error::new(
+                ErrorCode::WidthErr,
+                format!("{} does not fit in {} bits", value, width),
+            ));
+        }
+        return Ok(magnitude.clone());
+    }
+
+    let half = BigUint::one() << (width - 1);
+    let fits = match repr {
+        SignedRepr::TwosComplement => *magnitude <= half,
+        SignedRepr::OnesComplement | SignedRepr::SignMagnitude => *magnitude < half,
+    };
+    if !fits {
+        return Err(Error::new(
+            ErrorCode::WidthErr,
+            format!("{} does not fit in {} bits as {:?}", value, width, repr),
+        ));
+    }
+
+    Ok(match repr {
+        SignedRepr::TwosComplement => modulus - magnitude,
+        SignedRepr::OnesComplement => modulus - BigUint::one() - magnitude,
+        SignedRepr::SignMagnitude => half + magnitude,
+    })
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   decode
+//
+// NOTES:  Reads a `width` bit pattern back as a signed number
+// ARGS:
+//     pattern - bit pattern, must fit in `width` bits
+//     width - number of bits in the pattern
+//     repr - representation used for negative numbers
+// RETURN: The signed value, or an error if the pattern is too wide
+//
+pub fn decode(pattern: &BigUint, width: u32, repr: SignedRepr) -> Result<BigInt, Error> {
+    if width == 0 || pattern.bits() > u64::from(width) {
+        return Err(Error::new(
+            ErrorCode::WidthErr,
+            format!("Value does not fit in {} bits", width),
+        ));
+    }
+
+    let modulus = BigUint::one() << width;
+    let half = BigUint::one() << (width - 1);
+    if *pattern < half {
+        return Ok(BigInt::from(pattern.clone()));
+    }
+
+    let magnitude = match repr {
+        SignedRepr::TwosComplement => modulus - pattern,
+        SignedRepr::OnesComplement => modulus - BigUint::one() - pattern,
+        SignedRepr::SignMagnitude => pattern - half,
+    };
+    // Ones' complement and sign-magnitude both have a negative zero, which
+    // is still just zero once it is a number again.
+    if magnitude.is_zero() {
+        return Ok(BigInt::zero());
+    }
+    Ok(BigInt::from_biguint(Sign::Minus, magnitude))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   pattern_digits
+//
+// NOTES:
+//     Number of digits a full `width` bit pattern takes in `base`.  Only
+//     bases that are a power of two line up with bit boundaries, so other
+//     bases get no fixed digit count.
+// ARGS:
+//     width - number of bits in the pattern
+//     base - output base
+// RETURN: The digit count, or None if `base` is not a power of two
+//
+pub fn pattern_digits(width: u32, base: u32) -> Option<usize> {
+    if base < 2 || !base.is_power_of_two() {
+        return None;
+    }
+    let bits_per_digit = base.trailing_zeros();
+    Some(width.div_ceil(bits_per_digit) as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn int(v: i64) -> BigInt {
+        BigInt::from(v)
+    }
+
+    fn uint(v: u64) -> BigUint {
+        BigUint::from(v)
+    }
+
+    #[test]
+    fn test_encode() {
+        let twos = SignedRepr::TwosComplement;
+        assert_eq!(encode(&int(-1), 32, twos), Ok(uint(0xFFFF_FFFF)));
+        assert_eq!(encode(&int(-128), 8, twos), Ok(uint(0x80)));
+        assert_eq!(encode(&int(255), 8, twos), Ok(uint(0xFF)));
+        assert_eq!(
+            encode(&int(-129), 8, twos).map_err(|e| e.code),
+            Err(ErrorCode::WidthErr)
+        );
+        assert_eq!(
+            encode(&int(256), 8, twos).map_err(|e| e.code),
+            Err(ErrorCode::WidthErr)
+        );
+
+        assert_eq!(
+            encode(&int(-1), 8, SignedRepr::OnesComplement),
+            Ok(uint(0xFE))
+        );
+        assert_eq!(
+            encode(&int(-5), 8, SignedRepr::SignMagnitude),
+            Ok(uint(0x85))
+        );
+        assert_eq!(
+            encode(&int(-128), 8, SignedRepr::SignMagnitude).map_err(|e| e.code),
+            Err(ErrorCode::WidthErr)
+        );
+    }
+
+    #[test]
+    fn test_decode() {
+        let twos = SignedRepr::TwosComplement;
+        assert_eq!(decode(&uint(0xFFFF_FFFF), 32, twos), Ok(int(-1)));
+        assert_eq!(decode(&uint(0x7F), 8, twos), Ok(int(127)));
+        assert_eq!(decode(&uint(0x80), 8, twos), Ok(int(-128)));
+        assert_eq!(
+            decode(&uint(0x100), 8, twos).map_err(|e| e.code),
+            Err(ErrorCode::WidthErr)
+        );
+
+        assert_eq!(
+            decode(&uint(0xFF), 8, SignedRepr::OnesComplement),
+            Ok(int(0))
+        );
+        assert_eq!(
+            decode(&uint(0x85), 8, SignedRepr::SignMagnitude),
+            Ok(int(-5))
+        );
+    }
+
+    #[test]
+    fn test_signed_input() {
+        let hex = Alphabet::standard(16).unwrap();
+        let dec = Alphabet::standard(10).unwrap();
+        assert_eq!(convert_signed_to_base_10("-FF", &hex, '_'), Ok(int(-255)));
+        assert_eq!(convert_signed_to_base_10("1_000", &dec, '_'), Ok(int(1000)));
+        assert_eq!(pattern_digits(32, 16), Some(8));
+        assert_eq!(pattern_digits(10, 8), Some(4));
+        assert_eq!(pattern_digits(10, 10), None);
+    }
+}
@@ -0,0 +1,297 @@
+////////////////////////////////////////////////////////////////////////////////
+//  Module:   unicode.rs
+//
+//  Copyright © 2019 Zachary Nielsen
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+////////////////////////////////////////////////////////////////////////////////
+
+////////////////////////////////////////////////////////////////////////////////
+//  Namespaces
+////////////////////////////////////////////////////////////////////////////////
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+use std::str::FromStr;
+
+////////////////////////////////////////////////////////////////////////////////
+//  CODE
+////////////////////////////////////////////////////////////////////////////////
+
+/// A Unicode encoding form.  The LE and BE forms are the bytes of the code
+/// units in that order, the others are the code units themselves.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Utf {
+    Utf8,
+    Utf16,
+    Utf16Le,
+    Utf16Be,
+    Utf32,
+    Utf32Le,
+    Utf32Be,
+}
+
+impl Utf {
+    /// Name of the encoding, for the output row
+    pub fn name(&self) -> &'static str {
+        match self {
+            Utf::Utf8 => "UTF-8",
+            Utf::Utf16 => "UTF-16",
+            Utf::Utf16Le => "UTF-16LE",
+            Utf::Utf16Be => "UTF-16BE",
+            Utf::Utf32 => "UTF-32",
+            Utf::Utf32Le => "UTF-32LE",
+            Utf::Utf32Be => "UTF-32BE",
+        }
+    }
+
+    /// Bits in each number of the encoded text
+    pub fn unit_bits(&self) -> u32 {
+        match self {
+            Utf::Utf16 => 16,
+            Utf::Utf32 => 32,
+            _ => 8,
+        }
+    }
+
+    /// Bytes per code unit, and whether they are little endian, for the
+    /// forms written as bytes
+    fn byte_order(&self) -> Option<(usize, bool)> {
+        match self {
+            Utf::Utf16Le => Some((2, true)),
+            Utf::Utf16Be => Some((2, false)),
+            Utf::Utf32Le => Some((4, true)),
+            Utf::Utf32Be => Some((4, false)),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Utf {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name: String = s
+            .chars()
+            .filter(|c| *c != '-' && *c != '_')
+            .collect::<String>()
+            .to_ascii_lowercase();
+        match name.as_str() {
+            "utf8" => Ok(Utf::Utf8),
+            "utf16" => Ok(Utf::Utf16),
+            "utf16le" => Ok(Utf::Utf16Le),
+            "utf16be" => Ok(Utf::Utf16Be),
+            "utf32" => Ok(Utf::Utf32),
+            "utf32le" => Ok(Utf::Utf32Le),
+            "utf32be" => Ok(Utf::Utf32Be),
+            _ => Err(format!(
+                "Unknown Unicode encoding '{}'. Use utf8, utf16, utf16le, utf16be, utf32, utf32le, or utf32be",
+                s
+            )),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   encode
+//
+// NOTES:  Encodes text to the numbers of `utf`: code units, or their bytes
+// ARGS:
+//     text - the text to encode
+//     utf - encoding form
+// RETURN: The numbers, each `utf.unit_bits()` wide
+//
+pub fn encode(text: &str, utf: Utf) -> Vec<u32> {
+    let units: Vec<u32> = match utf {
+        Utf::Utf8 => return text.bytes().map(u32::from).collect(),
+        Utf::Utf16 | Utf::Utf16Le | Utf::Utf16Be => text.encode_utf16().map(u32::from).collect(),
+        Utf::Utf32 | Utf::Utf32Le | Utf::Utf32Be => text.chars().map(u32::from).collect(),
+    };
+    match utf.byte_order() {
+        Some((size, little)) => units
+            .iter()
+            .flat_map(|unit| {
+                let bytes = &unit.to_be_bytes()[4 - size..];
+                let mut bytes = bytes.to_vec();
+                if little {
+                    bytes.reverse();
+                }
+                bytes.into_iter().map(u32::from)
+            })
+            .collect(),
+        None => units,
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// NAME:   decode
+//
+// NOTES:
+//     Decodes the numbers of `utf` back to text.  Invalid sequences are an
+//     error, saying which number they start at (counting from 1).
+// ARGS:
+//     nums - code units, or their bytes for the LE and BE forms
+//     utf - encoding form
+// RETURN: The text, or what is wrong with the numbers
+//
+pub fn decode(nums: &[BigUint], utf: Utf) -> Result<String, String> {
+    let bits = utf.unit_bits();
+    let mut values = Vec::new();
+    for (idx, num) in nums.iter().enumerate() {
+        match num.to_u32().filter(|v| bits == 32 || *v >> bits == 0) {
+            Some(v) => values.push(v),
+            None => {
+                return Err(format!(
+                    "{} at {} does not fit in a {} bit {} unit",
+                    num,
+                    idx + 1,
+                    bits,
+                    utf.name()
+                ))
+            }
+        }
+    }
+
+    // Put the bytes back together into code units
+    let (units, unit_len) = match utf.byte_order() {
+        Some((size, little)) => {
+            if values.len() % size != 0 {
+                return Err(format!(
+                    "{} bytes do not make whole {} code units",
+                    values.len(),
+                    utf.name()
+                ));
+            }
+            let units = values
+                .chunks(size)
+                .map(|chunk| {
+                    let fold = |acc: u32, b: &u32| acc << 8 | b;
+                    if little {
+                        chunk.iter().rev().fold(0, fold)
+                    } else {
+                        chunk.iter().fold(0, fold)
+                    }
+                })
+                .collect();
+            (units, size)
+        }
+        None => (values, 1),
+    };
+
+    match utf {
+        Utf::Utf8 => {
+            let bytes: Vec<u8> = units.iter().map(|&b| b as u8).collect();
+            String::from_utf8(bytes).map_err(|e| {
+                let start = e.utf8_error().valid_up_to();
+                let len = e.utf8_error().error_len().unwrap_or(units.len() - start);
+                let sequence: Vec<String> = units[start..start + len]
+                    .iter()
+                    .map(|b| format!("{:02X}", b))
+                    .collect();
+                format!(
+                    "Invalid UTF-8 sequence {} at {}",
+                    sequence.join(" "),
+                    start + 1
+                )
+            })
+        }
+        Utf::Utf16 | Utf::Utf16Le | Utf::Utf16Be => {
+            let units16: Vec<u16> = units.iter().map(|&u| u as u16).collect();
+            let mut text = String::new();
+            let mut idx = 0;
+            for c in char::decode_utf16(units16.iter().copied()) {
+                match c {
+                    Ok(c) => {
+                        text.push(c);
+                        idx += c.len_utf16();
+                    }
+                    Err(e) => {
+                        return Err(format!(
+                            "Unpaired surrogate {:04X} at {}",
+                            e.unpaired_surrogate(),
+                            idx * unit_len + 1
+                        ))
+                    }
+                }
+            }
+            Ok(text)
+        }
+        Utf::Utf32 | Utf::Utf32Le | Utf::Utf32Be => units
+            .iter()
+            .enumerate()
+            .map(|(idx, &u)| {
+                char::from_u32(u).ok_or_else(|| {
+                    format!(
+                        "{:X} at {} is not a Unicode scalar value",
+                        u,
+                        idx * unit_len + 1
+                    )
+                })
+            })
+            .collect(),
+    }
+}
+
+/// The code point of `c`, like U+20AC
+pub fn code_point(c: char) -> String {
+    format!("U+{:04X}", u32::from(c))
+}
+
+/// The Unicode name of `c`, like EURO SIGN
+pub fn char_name(c: char) -> String {
+    match unicode_names2::name(c) {
+        Some(name) => name.to_string(),
+        None if c.is_control() => String::from("<control>"),
+        None => String::from("<unnamed>"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nums(values: &[u32]) -> Vec<BigUint> {
+        values.iter().map(|&v| BigUint::from(v)).collect()
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode("h€", Utf::Utf8), vec![0x68, 0xE2, 0x82, 0xAC]);
+        assert_eq!(encode("h€", Utf::Utf16), vec![0x68, 0x20AC]);
+        assert_eq!(encode("€", Utf::Utf16Le), vec![0xAC, 0x20]);
+        assert_eq!(encode("😀", Utf::Utf16), vec![0xD83D, 0xDE00]);
+        assert_eq!(encode("😀", Utf::Utf32Be), vec![0, 1, 0xF6, 0]);
+        assert_eq!("UTF-16le".parse(), Ok(Utf::Utf16Le));
+        assert!("utf7".parse::<Utf>().is_err());
+    }
+
+    #[test]
+    fn test_decode() {
+        for &utf in &[Utf::Utf8, Utf::Utf16, Utf::Utf16Be, Utf::Utf32Le] {
+            let units = nums(&encode("Grüße 😀", utf));
+            assert_eq!(decode(&units, utf), Ok(String::from("Grüße 😀")));
+        }
+
+        assert_eq!(
+            decode(&nums(&[0x41, 0xC3, 0x28]), Utf::Utf8),
+            Err(String::from("Invalid UTF-8 sequence C3 at 2"))
+        );
+        assert_eq!(
+            decode(&nums(&[0x41, 0xDE00]), Utf::Utf16),
+            Err(String::from("Unpaired surrogate DE00 at 2"))
+        );
+        assert!(decode(&nums(&[0x100]), Utf::Utf8).is_err());
+        assert!(decode(&nums(&[0x41, 0]), Utf::Utf32Le).is_err());
+        assert!(decode(&nums(&[0x110000]), Utf::Utf32).is_err());
+    }
+
+    #[test]
+    fn test_names() {
+        assert_eq!(code_point('€'), "U+20AC");
+        assert_eq!(char_name('€'), "EURO SIGN");
+        assert_eq!(char_name('\n'), "<control>");
+    }
+}
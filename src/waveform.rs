@@ -0,0 +1,234 @@
+////////////////////////////////////////////////////////////////////////////////
+//  Module:   waveform.rs
+//
+//  Copyright © 2019 Zachary Nielsen
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+////////////////////////////////////////////////////////////////////////////////
+
+////////////////////////////////////////////////////////////////////////////////
+//  Namespaces
+////////////////////////////////////////////////////////////////////////////////
+use crate::decimal::parse_decimal;
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{One, Zero};
+use std::str::FromStr;
+
+////////////////////////////////////////////////////////////////////////////////
+//  CODE
+////////////////////////////////////////////////////////////////////////////////
+
+/// Preamble keywords, long form then the short form a scope may send
+const KEYWORDS: &[(&str, &str)] = &[
+    ("YMULT", "YMU"),
+    ("YOFF", "YOF"),
+    ("YZERO", "YZE"),
+    ("YUNIT", "YUN"),
+    ("XINCR", "XIN"),
+    ("XZERO", "XZE"),
+    ("XUNIT", "XUN"),
+    ("PT_OFF", "PT_O"),
+];
+
+/// The scaling of a scope waveform, as sent by WFMOutpre?.  Sample n with
+/// code c is at time XZERO + XINCR * (n - PT_OFF), with the value
+/// (c - YOFF) * YMULT + YZERO.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Preamble {
+    pub ymult: BigRational,
+    pub yoff: BigRational,
+    pub yzero: BigRational,
+    pub yunit: String,
+    pub xincr: BigRational,
+    pub xzero: BigRational,
+    pub xunit: String,
+    pub pt_off: BigRational,
+}
+
+impl Default for Preamble {
+    /// Codes and sample numbers as they are, in volts and seconds
+    fn default() -> Self {
+        Preamble {
+            ymult: BigRational::one(),
+            yoff: BigRational::zero(),
+            yzero: BigRational::zero(),
+            yunit: String::from("V"),
+            xincr: BigRational::one(),
+            xzero: BigRational::zero(),
+            xunit: String::from("s"),
+            pt_off: BigRational::zero(),
+        }
+    }
+}
+
+impl Preamble {
+    ////////////////////////////////////////////////////////////////////////////
+    // NAME:   set
+    //
+    // NOTES:
+    //     Sets one parameter by its keyword, long (YMULT) or short (YMU),
+    //     in any case.  Units may be quoted.
+    // ARGS:
+    //     keyword - the parameter
+    //     value - its value, as the scope writes it, e.g. 4.0000E-3
+    // RETURN: Whether the keyword is a scaling one, or an error if the
+    //         value can not be read
+    //
+    pub fn set(&mut self, keyword: &str, value: &str) -> Result<bool, String> {
+        let keyword = keyword.to_ascii_uppercase();
+        let name = match KEYWORDS
+            .iter()
+            .find(|(long, short)| keyword.starts_with(short) && long.starts_with(&keyword))
+        {
+            Some((long, _)) => *long,
+            None => return Ok(false),
+        };
+
+        let value = value.trim();
+        if name.ends_with("UNIT") {
+            let unit = value.trim_matches('"').to_string();
+            if name == "YUNIT" {
+                self.yunit = unit;
+            } else {
+                self.xunit = unit;
+            }
+            return Ok(true);
+        }
+
+        let number = match parse_decimal(value) {
+            Some(v) => v,
+            None => return Err(format!("Could not read {} {}", name, value)),
+        };
+        match name {
+            "YMULT" if number.is_zero() => return Err(String::from("YMULT can not be 0")),
+            "YMULT" => self.ymult = number,
+            "YOFF" => self.yoff = number,
+            "YZERO" => self.yzero = number,
+            "XINCR" => self.xincr = number,
+            "XZERO" => self.xzero = number,
+            _ => self.pt_off = number,
+        }
+        Ok(true)
+    }
+
+    /// The value a sample code stands for, e.g. in volts
+    pub fn value(&self, code: &BigInt) -> BigRational {
+        (BigRational::from_integer(code.clone()) - &self.yoff) * &self.ymult + &self.yzero
+    }
+
+    /// The time, or other X value, of sample `index` of the record
+    pub fn time(&self, index: usize) -> BigRational {
+        &self.xzero + &self.xincr * (BigRational::from_integer(BigInt::from(index)) - &self.pt_off)
+    }
+
+    /// The code nearest to a value, the inverse of `value`
+    pub fn code(&self, value: &BigRational) -> BigInt {
+        ((value - &self.yzero) / &self.ymult + &self.yoff)
+            .round()
+            .to_integer()
+    }
+}
+
+impl FromStr for Preamble {
+    type Err = String;
+
+    ////////////////////////////////////////////////////////////////////////////
+    // NAME:   from_str
+    //
+    // NOTES:
+    //     Reads a WFMOutpre? response with headers on, e.g.
+    //     :WFMOUTPRE:BYT_NR 1;...;YMULT 4.0000E-3;YOFF 0.0E+0;...  Keywords
+    //     it does not use, like BYT_NR and WFID, are skipped.
+    // ARGS:
+    //     s - the response
+    // RETURN: The preamble, or what is wrong with the response
+    //
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut preamble = Preamble::default();
+        let mut found = false;
+        for part in split_unquoted(s) {
+            let (keyword, value) = match part.trim().split_once(char::is_whitespace) {
+                Some(v) => v,
+                None => continue,
+            };
+            // Drop the header path, e.g. :WFMOUTPRE:
+            let keyword = keyword.rsplit(':').next().unwrap_or(keyword);
+            found |= preamble.set(keyword, value)?;
+        }
+        if !found {
+            return Err(String::from(
+                "No scaling keywords (YMULT, XINCR, ...) in the preamble, send it with HEADER ON",
+            ));
+        }
+        Ok(preamble)
+    }
+}
+
+/// Splits a response at the semicolons outside quoted strings
+fn split_unquoted(s: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut quoted = false;
+    let mut start = 0;
+    for (idx, c) in s.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ';' if !quoted => {
+                parts.push(&s[start..idx]);
+                start = idx + 1;
+            }
+            _ => (),
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RESPONSE: &str = ":WFMOUTPRE:BYT_NR 1;BIT_NR 8;ENCDG BINARY;BN_FMT RI;BYT_OR MSB;\
+        WFID \"Ch1, DC coupling, 100.0mV/div; 4.000us/div, 10000 points\";NR_PT 10000;\
+        PT_FMT Y;XUNIT \"s\";XINCR 4.0000E-9;XZERO -20.0000E-6;PT_OFF 0;YUNIT \"V\";\
+        YMULT 4.0000E-3;YOFF 28.0000E+0;YZERO 0.0E+0";
+
+    fn real(text: &str) -> BigRational {
+        parse_decimal(text).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        let preamble: Preamble = RESPONSE.parse().unwrap();
+        assert_eq!(preamble.ymult, real("0.004"));
+        assert_eq!(preamble.yoff, real("28"));
+        assert_eq!(preamble.xzero, real("-0.00002"));
+        assert_eq!(preamble.yunit, "V");
+
+        let short: Preamble = ":WFMO:YMU 1.0E-2;YOF -5;YZE 1;XIN 1.0E-3;PT_O 10;YUN \"A\""
+            .parse()
+            .unwrap();
+        assert_eq!(short.ymult, real("0.01"));
+        assert_eq!(short.pt_off, real("10"));
+        assert_eq!(short.yunit, "A");
+
+        assert!("1;8;BIN;RI;MSB".parse::<Preamble>().is_err());
+        assert!("YMULT 0".parse::<Preamble>().is_err());
+        assert!("YMULT fast".parse::<Preamble>().is_err());
+    }
+
+    #[test]
+    fn test_scaling() {
+        let preamble: Preamble = RESPONSE.parse().unwrap();
+        assert_eq!(preamble.value(&BigInt::from(53)), real("0.1"));
+        assert_eq!(preamble.value(&BigInt::from(-100)), real("-0.512"));
+        assert_eq!(preamble.code(&real("0.1")), BigInt::from(53));
+        assert_eq!(preamble.code(&real("0.1011")), BigInt::from(53));
+        assert_eq!(preamble.time(0), real("-0.00002"));
+        assert_eq!(preamble.time(5000), real("0"));
+    }
+}